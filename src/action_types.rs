@@ -0,0 +1,210 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::{env, near_bindgen, require, AccountId};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Maximum length of an action type name, in bytes.
+const MAX_ACTION_TYPE_LENGTH: usize = 64;
+/// Maximum length of an action type description, in bytes.
+const MAX_DESCRIPTION_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+pub struct CoretoTPActionTypeRequestData {
+    pub(crate) action_type: String,
+    pub(crate) description: String,
+    pub(crate) min_trust: f32,
+    pub(crate) max_trust: f32,
+    pub(crate) min_performance: f32,
+    pub(crate) max_performance: f32,
+    pub(crate) default_weight: f32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPActionTypeStatus {
+    Active,
+    /// No longer accepts writes; existing actions still count.
+    Deprecated,
+    /// No longer accepts writes and is left out of the user scores.
+    Retired,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPActionType {
+    pub(crate) description: String,
+    pub(crate) min_trust: f32,
+    pub(crate) max_trust: f32,
+    pub(crate) min_performance: f32,
+    pub(crate) max_performance: f32,
+    /// Multiplier applied to the type's values in the user scores.
+    pub(crate) weight: f32,
+    pub(crate) status: CoretoTPActionTypeStatus,
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Registers an action type the source can write. Callable by the
+    /// source or a SourceManager; the source pays for its storage.
+    pub fn register_action_type(&mut self, source: AccountId, data: CoretoTPActionTypeRequestData) {
+        self.assert_source_admin(&source);
+        require!(
+            !data.action_type.is_empty() && data.action_type.len() <= MAX_ACTION_TYPE_LENGTH,
+            "Invalid action type name."
+        );
+        require!(data.description.len() <= MAX_DESCRIPTION_LENGTH, "Description too long.");
+        require!(
+            data.min_trust.is_finite() && data.max_trust.is_finite() && data.min_trust <= data.max_trust,
+            "Invalid trust bounds."
+        );
+        require!(
+            data.min_performance.is_finite() && data.max_performance.is_finite() && data.min_performance <= data.max_performance,
+            "Invalid performance bounds."
+        );
+        require!(
+            data.default_weight.is_finite() && data.default_weight >= 0.0,
+            "Invalid weight."
+        );
+
+        let initial_storage_usage = env::storage_usage();
+        let mut action_types = self.source_action_types(&source);
+        require!(
+            action_types.get(&data.action_type).is_none(),
+            "Action type already exists."
+        );
+
+        CoretoTPEvent::ActionTypeRegistered {
+            source: &source,
+            action_type: &data.action_type,
+            weight: data.default_weight,
+        }
+        .emit();
+
+        action_types.insert(&data.action_type, &CoretoTPActionType {
+            description: data.description,
+            min_trust: data.min_trust,
+            max_trust: data.max_trust,
+            min_performance: data.min_performance,
+            max_performance: data.max_performance,
+            weight: data.default_weight,
+            status: CoretoTPActionTypeStatus::Active,
+        });
+        self.source_actions.insert(&source, &action_types);
+        self.charge_storage(&source, initial_storage_usage);
+    }
+
+    pub fn set_action_type_weight(&mut self, source: AccountId, action_type: String, weight: f32) {
+        self.assert_role(CoretoTPRole::WeightManager);
+        self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
+        require!(weight.is_finite() && weight >= 0.0, "Invalid weight.");
+
+        let action_types = self.source_actions.get(&source);
+        let mut registered = action_types.as_ref().and_then(|action_types| action_types.get(&action_type));
+        require!(registered.is_some(), "Action type not registered.");
+
+        registered.as_mut().unwrap().weight = weight;
+        action_types.unwrap().insert(&action_type, &registered.unwrap());
+
+        CoretoTPEvent::ActionTypeWeightUpdated { source: &source, action_type: &action_type, weight }.emit();
+    }
+
+    /// Deprecates, retires or reactivates an action type. Callable by the
+    /// source or a SourceManager.
+    pub fn set_action_type_status(&mut self, source: AccountId, action_type: String, status: CoretoTPActionTypeStatus) {
+        self.assert_source_admin(&source);
+        let initial_storage_usage = env::storage_usage();
+
+        let mut action_types = self.source_action_types(&source);
+        let registered = action_types.get(&action_type);
+        require!(registered.is_some(), "Action type not registered.");
+
+        let mut registered = registered.unwrap();
+        registered.status = status;
+        action_types.insert(&action_type, &registered);
+        self.charge_storage(&source, initial_storage_usage);
+
+        CoretoTPEvent::ActionTypeStatusUpdated { source: &source, action_type: &action_type, status }.emit();
+    }
+
+    pub fn get_source_action_type_details(&self, source: AccountId) -> Vec<(String, CoretoTPActionType)> {
+        require!(
+            self.sources.contains_key(&source) && self.source_actions.contains_key(&source),
+            "Source not found."
+        );
+
+        self.source_actions.get(&source).unwrap().to_vec()
+    }
+
+    pub fn get_source_action_type(&self, source: AccountId, action_type: String) -> Option<CoretoTPActionType> {
+        self.source_actions
+            .get(&source)
+            .and_then(|action_types| action_types.get(&action_type))
+    }
+}
+
+impl CoretoTPLedger {
+    pub(crate) fn source_action_types(&self, source: &AccountId) -> UnorderedMap<String, CoretoTPActionType> {
+        self.source_actions
+            .get(source)
+            .unwrap_or_else(|| UnorderedMap::new(StorageKeys::SourceActionsSetKey {
+                source_hash: env::sha256_array(source.as_bytes()),
+            }))
+    }
+
+    /// Checks a write against the source's registered action types.
+    pub(crate) fn assert_valid_action(
+        action_types: &UnorderedMap<String, CoretoTPActionType>,
+        action_type: &String,
+        trust: f32,
+        performance: f32,
+    ) {
+        require_ok(Self::validate_action(action_types, action_type, trust, performance));
+    }
+
+    pub(crate) fn validate_action(
+        action_types: &UnorderedMap<String, CoretoTPActionType>,
+        action_type: &String,
+        trust: f32,
+        performance: f32,
+    ) -> Result<(), CoretoTPErrorCode> {
+        match action_types.get(action_type).map(|registered| registered.status) {
+            None => return Err(CoretoTPErrorCode::ActionTypeNotRegistered),
+            Some(CoretoTPActionTypeStatus::Deprecated) => return Err(CoretoTPErrorCode::ActionTypeDeprecated),
+            Some(CoretoTPActionTypeStatus::Retired) => return Err(CoretoTPErrorCode::ActionTypeRetired),
+            Some(CoretoTPActionTypeStatus::Active) => (),
+        }
+
+        Self::validate_action_bounds(action_types, action_type, trust, performance)
+    }
+
+    /// Checks the values against the registered bounds of the action type,
+    /// whatever its status.
+    pub(crate) fn assert_action_bounds(
+        action_types: &UnorderedMap<String, CoretoTPActionType>,
+        action_type: &String,
+        trust: f32,
+        performance: f32,
+    ) {
+        require_ok(Self::validate_action_bounds(action_types, action_type, trust, performance));
+    }
+
+    fn validate_action_bounds(
+        action_types: &UnorderedMap<String, CoretoTPActionType>,
+        action_type: &String,
+        trust: f32,
+        performance: f32,
+    ) -> Result<(), CoretoTPErrorCode> {
+        let registered = action_types
+            .get(action_type)
+            .ok_or(CoretoTPErrorCode::ActionTypeNotRegistered)?;
+
+        if !(trust >= registered.min_trust && trust <= registered.max_trust) {
+            return Err(CoretoTPErrorCode::TrustOutOfBounds);
+        }
+        if !(performance >= registered.min_performance && performance <= registered.max_performance) {
+            return Err(CoretoTPErrorCode::PerformanceOutOfBounds);
+        }
+
+        Ok(())
+    }
+}
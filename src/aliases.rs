@@ -0,0 +1,94 @@
+use near_sdk::{near_bindgen, require};
+
+use crate::*;
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Links `alias` to `canonical_did`. Queries on either DID return the
+    /// combined history and scores of both; stored actions are not moved, so
+    /// the link can be undone with `unmerge_did`.
+    pub fn merge_did(&mut self, canonical_did: String, alias: String) {
+        self.assert_role(CoretoTPRole::Moderator);
+        self.assert_not_paused(CoretoTPPauseCategory::DidRegistry);
+        let canonical_did = self.did_key(&canonical_did);
+        let alias = self.did_key(&alias);
+
+        require!(canonical_did != alias, "Cannot merge a DID into itself.");
+        require!(!self.did_aliases.contains_key(&canonical_did), "Canonical DID is an alias.");
+        require!(!self.did_aliases.contains_key(&alias), "DID already merged.");
+        require!(!self.canonical_dids.contains_key(&alias), "DID has aliases.");
+
+        let mut aliases = self.canonical_dids.get(&canonical_did).unwrap_or_default();
+        aliases.push(alias.clone());
+        self.canonical_dids.insert(&canonical_did, &aliases);
+        self.did_aliases.insert(&alias, &canonical_did);
+
+        CoretoTPEvent::DidMerged { canonical_did: &canonical_did, alias: &alias }.emit();
+    }
+
+    /// Removes the link between `alias` and its canonical DID.
+    pub fn unmerge_did(&mut self, alias: String) {
+        self.assert_role(CoretoTPRole::Moderator);
+        self.assert_not_paused(CoretoTPPauseCategory::DidRegistry);
+        let alias = self.did_key(&alias);
+
+        let canonical_did = self.did_aliases.get(&alias);
+        require!(canonical_did.is_some(), "DID not merged.");
+
+        let canonical_did = canonical_did.unwrap();
+        self.unlink_did(&alias);
+
+        CoretoTPEvent::DidUnmerged { canonical_did: &canonical_did, alias: &alias }.emit();
+    }
+
+    /// The canonical DID `account_did` is merged into, or the DID itself.
+    pub fn get_canonical_did(&self, account_did: String) -> String {
+        self.canonical_did(&self.did_key(&account_did))
+    }
+
+    pub fn get_did_aliases(&self, account_did: String) -> Vec<String> {
+        let canonical_did = self.canonical_did(&self.did_key(&account_did));
+        self.canonical_dids.get(&canonical_did).unwrap_or_default()
+    }
+}
+
+impl CoretoTPLedger {
+    /// Removes every merge the DID takes part in, as an alias or as a
+    /// canonical DID.
+    pub(crate) fn unlink_did(&mut self, account_did: &String) {
+        if let Some(canonical_did) = self.did_aliases.remove(account_did) {
+            let mut aliases = self.canonical_dids.get(&canonical_did).unwrap();
+            aliases.retain(|merged| merged != account_did);
+            if aliases.is_empty() {
+                self.canonical_dids.remove(&canonical_did);
+            } else {
+                self.canonical_dids.insert(&canonical_did, &aliases);
+            }
+        }
+
+        for alias in self.canonical_dids.remove(account_did).unwrap_or_default() {
+            self.did_aliases.remove(&alias);
+        }
+    }
+
+    pub(crate) fn canonical_did(&self, account_did: &String) -> String {
+        self.did_aliases.get(account_did).unwrap_or_else(|| account_did.clone())
+    }
+
+    /// The DIDs whose histories are combined for `account_did`: its canonical
+    /// DID first, then every alias.
+    pub(crate) fn merged_dids(&self, account_did: &String) -> Vec<String> {
+        let canonical_did = self.canonical_did(account_did);
+        let mut dids = self.canonical_dids.get(&canonical_did).unwrap_or_default();
+        dids.insert(0, canonical_did);
+        dids
+    }
+
+    /// Actions recorded for `account_did` and the DIDs merged with it.
+    pub(crate) fn merged_stats(&self, account_did: &String) -> Vec<CoretoTPStat> {
+        self.merged_dids(account_did)
+            .iter()
+            .filter_map(|did| self.tp_infos.get(did))
+            .collect()
+    }
+}
@@ -0,0 +1,231 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{env, near_bindgen, require, AccountId, Balance, BlockHeight};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::storage::BATCH_STORAGE_ESTIMATE;
+use crate::*;
+
+/// Maximum number of actions returned by one paginated view call.
+const MAX_BATCH_ACTIONS_PAGE: u64 = 100;
+
+/// Receipt of a batch write.
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPBatch {
+    pub(crate) id: u64,
+    pub(crate) source: AccountId,
+    /// Number of processed items, including rejected and duplicate ones.
+    /// Items left unprocessed because the gas ran out are not counted.
+    pub(crate) items_count: u64,
+    /// Number of actions written.
+    pub(crate) actions_count: u64,
+    /// sha256 hash of the Borsh-serialized processed items.
+    pub(crate) content_hash: Base58CryptoHash,
+    pub(crate) block_height: BlockHeight,
+    pub(crate) block_date: String,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPItemStatus {
+    Accepted,
+    /// The source already wrote an action with the same DID, type and
+    /// identifier; the item was skipped.
+    Duplicate,
+    Rejected,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct CoretoTPItemResult {
+    pub(crate) status: CoretoTPItemStatus,
+    pub(crate) error: Option<CoretoTPErrorCode>,
+}
+
+/// Outcome of a batch write, with one result per processed item in batch
+/// order.
+#[derive(Serialize)]
+pub struct CoretoTPBatchResult {
+    pub(crate) batch_id: u64,
+    pub(crate) results: Vec<CoretoTPItemResult>,
+    /// Index of the first item left unprocessed because the gas ran out.
+    /// The remaining items can be submitted again from there.
+    pub(crate) next_index: Option<u64>,
+}
+
+impl CoretoTPItemResult {
+    pub(crate) fn accepted() -> Self {
+        Self { status: CoretoTPItemStatus::Accepted, error: None }
+    }
+
+    pub(crate) fn duplicate() -> Self {
+        Self { status: CoretoTPItemStatus::Duplicate, error: Some(CoretoTPErrorCode::DuplicateAction) }
+    }
+
+    pub(crate) fn rejected(error: CoretoTPErrorCode) -> Self {
+        Self { status: CoretoTPItemStatus::Rejected, error: Some(error) }
+    }
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Dry run of `save_actions_batch` for a batch sent by `submitter`, a
+    /// source or one of its submitters. Returns the result each item would
+    /// get with `partial` set, without writing anything. Items the source's
+    /// storage balance is estimated not to cover are rejected with
+    /// `InsufficientStorage`. Like every view,
+    /// it works while ingestion is paused; `is_paused` tells if it is.
+    pub fn validate_actions_batch(&self, submitter: AccountId, batch: Vec<CoretoTPActionRequestData>) -> Vec<CoretoTPItemResult> {
+        let source_id = self.source_of(&submitter);
+        require!(source_id.is_some(), "Invalid signer wallet.");
+
+        let source_id = source_id.unwrap();
+        let action_types = self.source_action_types(&source_id);
+        let mut batch_keys = HashSet::new();
+        let storage_available = self.storage_available(&source_id);
+        let mut storage_needed = BATCH_STORAGE_ESTIMATE;
+
+        batch
+            .iter()
+            .map(|data| {
+                let account_did = match self.validate_batch_item(&source_id, &action_types, data) {
+                    Ok(account_did) => account_did,
+                    Err(error) => return CoretoTPItemResult::rejected(error),
+                };
+
+                let item_storage = Self::estimate_item_storage(&account_did, data);
+                if self.is_duplicate_action(&source_id, &account_did, &data.action_type, &data.identifier)
+                    || !batch_keys.insert((account_did, &data.action_type, &data.identifier))
+                {
+                    return CoretoTPItemResult::duplicate();
+                }
+
+                if Balance::from(storage_needed + item_storage) * env::storage_byte_cost() > storage_available {
+                    return CoretoTPItemResult::rejected(CoretoTPErrorCode::InsufficientStorage);
+                }
+                storage_needed += item_storage;
+
+                CoretoTPItemResult::accepted()
+            })
+            .collect()
+    }
+
+    pub fn get_batch(&self, batch_id: u64) -> Option<CoretoTPBatch> {
+        self.batches.get(batch_id)
+    }
+
+    /// Actions written by the batch, in batch order. Actions since purged
+    /// or redacted, and actions of DIDs that withhold their scores, are
+    /// left out.
+    pub fn get_batch_actions(&self, batch_id: u64, from_index: Option<u64>, limit: Option<u64>) -> Vec<CoretoTPAction> {
+        let action_refs = self.batch_actions.get(&batch_id);
+        require!(action_refs.is_some(), "Batch not found.");
+
+        let from_index = from_index.unwrap_or(0) as usize;
+        let limit = limit.unwrap_or(MAX_BATCH_ACTIONS_PAGE).min(MAX_BATCH_ACTIONS_PAGE) as usize;
+        let source = self.batches.get(batch_id).unwrap().source;
+
+        action_refs
+            .unwrap()
+            .iter()
+            .skip(from_index)
+            .take(limit)
+            .filter(|(_, action_ref)| !self.is_score_withheld(&action_ref.account_did))
+            .filter_map(|(identifier, action_ref)| self.batch_action(batch_id, &source, identifier, action_ref))
+            .map(|(_, action)| action)
+            .collect()
+    }
+
+    /// Retracts every action the calling source wrote in the batch.
+    pub fn retract_batch(&mut self, batch_id: u64, reason: String) {
+        self.assert_not_paused(CoretoTPPauseCategory::Corrections);
+        let (source_id, _) = self.resolve_source();
+        let initial_storage_usage = env::storage_usage();
+        require!(
+            self.batches.get(batch_id).is_some_and(|batch| batch.source == source_id),
+            "Batch not found."
+        );
+
+        let mut retracted = 0;
+        for (identifier, action_ref) in self.batch_actions.get(&batch_id).unwrap().iter() {
+            let (index, mut action) = match self.batch_action(batch_id, &source_id, identifier, action_ref) {
+                Some(found) => found,
+                None => continue,
+            };
+            if action.retraction.is_some() {
+                continue;
+            }
+
+            action.retraction = Some(CoretoTPRetraction {
+                reason: reason.clone(),
+                block_date: env::block_timestamp().to_string(),
+            });
+            let mut tp_info = self.tp_infos.get(&action_ref.account_did).unwrap();
+            tp_info.actions.replace(index, &action);
+            retracted += 1;
+        }
+        self.charge_storage(&source_id, initial_storage_usage);
+
+        CoretoTPEvent::BatchRetracted {
+            source: &source_id,
+            batch_id,
+            actions_count: retracted,
+            reason: &reason,
+        }
+        .emit();
+    }
+}
+
+impl CoretoTPLedger {
+    /// Finds the action a batch wrote under `identifier`, with its
+    /// position.
+    fn batch_action(
+        &self,
+        batch_id: u64,
+        source: &AccountId,
+        identifier: &String,
+        action_ref: &CoretoTPActionRef,
+    ) -> Option<(u64, CoretoTPAction)> {
+        let tp_info = self.tp_infos.get(&action_ref.account_did)?;
+        let index = Self::find_action(&tp_info, source, identifier, action_ref)?;
+
+        tp_info.actions
+            .get(index)
+            .filter(|action| action.batch_id == Some(batch_id))
+            .map(|action| (index, action))
+    }
+
+    /// Checks a batch item the source wants to write. Returns the key of its
+    /// DID.
+    pub(crate) fn validate_batch_item(
+        &self,
+        source_id: &AccountId,
+        action_types: &UnorderedMap<String, CoretoTPActionType>,
+        data: &CoretoTPActionRequestData,
+    ) -> Result<String, CoretoTPErrorCode> {
+        Self::validate_action(action_types, &data.action_type, data.trust, data.performance)?;
+
+        let account_did = self.try_did_key(&data.account_did)?;
+        self.check_source_consented(&account_did, source_id)?;
+
+        Ok(account_did)
+    }
+
+    /// Whether the source already wrote an action of this type under the
+    /// identifier for the DID.
+    pub(crate) fn is_duplicate_action(
+        &self,
+        source_id: &AccountId,
+        account_did: &String,
+        action_type: &String,
+        identifier: &str,
+    ) -> bool {
+        self.action_index
+            .get(&(source_id.clone(), identifier.to_string()))
+            .is_some_and(|refs| {
+                refs.iter().any(|action_ref| {
+                    action_ref.account_did == *account_did && action_ref.action_type == *action_type
+                })
+            })
+    }
+}
@@ -0,0 +1,132 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::Vector;
+use near_sdk::{env, near_bindgen, require, AccountId};
+use serde::{Serialize, Serializer};
+
+use crate::*;
+
+/// Maximum number of consents returned by one paginated view call.
+const MAX_CONSENT_HISTORY_PAGE: u64 = 100;
+/// Maximum number of sources a DID can opt out of.
+const MAX_EXCLUDED_SOURCES: usize = 20;
+
+/// A DID's consent to reputation tracking, as last set by its controller.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+pub struct CoretoTPConsent {
+    /// Sources whose writes for the DID are rejected.
+    pub(crate) excluded_sources: Vec<AccountId>,
+    /// Replaces the DID's scores with `"withheld"` in the score views.
+    pub(crate) scores_withheld: bool,
+    pub(crate) changed_by: AccountId,
+    pub(crate) block_date: String,
+}
+
+/// Result of a score view: the score, or `"withheld"` if the DID opted out
+/// of public score exposure.
+#[derive(PartialEq, Debug)]
+pub enum CoretoTPScore {
+    Value(f32),
+    Withheld,
+}
+
+impl Serialize for CoretoTPScore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CoretoTPScore::Value(value) => serializer.serialize_f32(*value),
+            CoretoTPScore::Withheld => serializer.serialize_str("withheld"),
+        }
+    }
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Replaces the DID's consent. Called by the DID controller; every
+    /// change is kept in the consent history, and the attached deposit has
+    /// to cover its storage. The rest is refunded.
+    #[payable]
+    pub fn set_consent(&mut self, account_did: String, excluded_sources: Vec<AccountId>, scores_withheld: bool) {
+        self.assert_not_paused(CoretoTPPauseCategory::DidRegistry);
+        let account_did = self.normalize_did(&account_did);
+        require!(
+            self.is_did_controller(&account_did, &env::predecessor_account_id()),
+            "Not the DID controller."
+        );
+        require!(excluded_sources.len() <= MAX_EXCLUDED_SOURCES, "Too many excluded sources.");
+
+        let account_did = self.hash_did(&account_did);
+        let initial_storage_usage = env::storage_usage();
+        CoretoTPEvent::ConsentUpdated {
+            account_did: &account_did,
+            excluded_sources: &excluded_sources,
+            scores_withheld,
+        }
+        .emit();
+
+        let consent = CoretoTPConsent {
+            excluded_sources,
+            scores_withheld,
+            changed_by: env::predecessor_account_id(),
+            block_date: env::block_timestamp().to_string(),
+        };
+
+        let mut consents = self.consents.get(&account_did).unwrap_or_else(|| {
+            Vector::new(StorageKeys::ConsentHistoryKey {
+                account_hash: env::sha256_array(account_did.as_bytes()),
+            })
+        });
+        consents.push(&consent);
+        self.consents.insert(&account_did, &consents);
+
+        self.charge_attached_deposit(initial_storage_usage);
+    }
+
+    pub fn get_consent(&self, account_did: String) -> Option<CoretoTPConsent> {
+        self.consent(&self.did_key(&account_did))
+    }
+
+    /// The consents the DID's controllers set, oldest first.
+    pub fn get_consent_history(&self, account_did: String, from_index: Option<u64>, limit: Option<u64>) -> Vec<CoretoTPConsent> {
+        let consents = match self.consents.get(&self.did_key(&account_did)) {
+            Some(consents) => consents,
+            None => return Vec::new(),
+        };
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(MAX_CONSENT_HISTORY_PAGE).min(MAX_CONSENT_HISTORY_PAGE);
+
+        (from_index..consents.len().min(from_index.saturating_add(limit)))
+            .filter_map(|index| consents.get(index))
+            .collect()
+    }
+}
+
+impl CoretoTPLedger {
+    pub(crate) fn consent(&self, account_did: &String) -> Option<CoretoTPConsent> {
+        self.consents
+            .get(account_did)
+            .and_then(|consents| consents.len().checked_sub(1).and_then(|last| consents.get(last)))
+    }
+
+    pub(crate) fn assert_source_consented(&self, account_did: &String, source: &AccountId) {
+        require_ok(self.check_source_consented(account_did, source));
+    }
+
+    /// Fails if the DID, or a DID merged with it, opted out of the source.
+    pub(crate) fn check_source_consented(&self, account_did: &String, source: &AccountId) -> Result<(), CoretoTPErrorCode> {
+        let opted_out = self
+            .merged_dids(account_did)
+            .iter()
+            .any(|did| self.consent(did).is_some_and(|consent| consent.excluded_sources.contains(source)));
+
+        if opted_out {
+            return Err(CoretoTPErrorCode::DidOptedOut);
+        }
+        Ok(())
+    }
+
+    /// Whether the DID, or a DID merged with it, withholds its scores.
+    pub(crate) fn is_score_withheld(&self, account_did: &String) -> bool {
+        self.merged_dids(account_did)
+            .iter()
+            .any(|did| self.consent(did).is_some_and(|consent| consent.scores_withheld))
+    }
+}
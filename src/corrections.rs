@@ -0,0 +1,174 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+use serde::Serialize;
+
+use crate::*;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+pub struct CoretoTPRetraction {
+    pub(crate) reason: String,
+    pub(crate) block_date: String,
+}
+
+/// A superseded version of an amended action, with the reason and block
+/// date of the amendment that replaced it.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+pub struct CoretoTPActionRevision {
+    pub(crate) trust: f32,
+    pub(crate) performance: f32,
+    pub(crate) reason: String,
+    pub(crate) block_date: String,
+}
+
+/// Where an action written under a source identifier is stored. `index` is
+/// a hint, as purging another source's actions shifts positions.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CoretoTPActionRef {
+    pub(crate) account_did: String,
+    pub(crate) action_type: String,
+    pub(crate) index: u64,
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Marks the calling source's actions with `identifier` as retracted.
+    /// They stay in the history but no longer count in any score.
+    pub fn retract_action(&mut self, identifier: String, reason: String) {
+        self.assert_not_paused(CoretoTPPauseCategory::Corrections);
+        let (source_id, _) = self.resolve_source();
+        let initial_storage_usage = env::storage_usage();
+
+        let retracted = self.update_actions(&source_id, &identifier, |action| {
+            if action.retraction.is_some() {
+                return false;
+            }
+
+            action.retraction = Some(CoretoTPRetraction {
+                reason: reason.clone(),
+                block_date: env::block_timestamp().to_string(),
+            });
+            true
+        });
+        require!(retracted > 0, "Action already retracted.");
+        self.charge_storage(&source_id, initial_storage_usage);
+
+        CoretoTPEvent::ActionRetracted { source: &source_id, identifier: &identifier, reason: &reason }.emit();
+    }
+
+    /// Corrects the values of the calling source's actions with
+    /// `identifier`. The previous values are kept in the action's revisions.
+    pub fn amend_action(&mut self, identifier: String, new_trust: f32, new_performance: f32, reason: String) {
+        self.assert_not_paused(CoretoTPPauseCategory::Corrections);
+        let (source_id, _) = self.resolve_source();
+        let initial_storage_usage = env::storage_usage();
+        let action_types = self.source_action_types(&source_id);
+
+        self.update_actions(&source_id, &identifier, |action| {
+            require!(action.retraction.is_none(), "Action is retracted.");
+            Self::assert_action_bounds(&action_types, &action.action_type, new_trust, new_performance);
+
+            action.revisions.push(CoretoTPActionRevision {
+                trust: action.trust,
+                performance: action.performance,
+                reason: reason.clone(),
+                block_date: env::block_timestamp().to_string(),
+            });
+            action.trust = new_trust;
+            action.performance = new_performance;
+            true
+        });
+        self.charge_storage(&source_id, initial_storage_usage);
+
+        CoretoTPEvent::ActionAmended {
+            source: &source_id,
+            identifier: &identifier,
+            trust: new_trust,
+            performance: new_performance,
+            reason: &reason,
+        }
+        .emit();
+    }
+
+    /// Returns the source's actions with `identifier`, including retracted
+    /// ones, each with its revision history. Actions of DIDs that withhold
+    /// their scores are left out.
+    pub fn get_action_history(&self, source: AccountId, identifier: String) -> Vec<CoretoTPAction> {
+        let refs = self.action_index.get(&(source.clone(), identifier.clone()));
+        require!(refs.is_some(), "Action not found.");
+
+        refs.unwrap()
+            .iter()
+            .filter(|action_ref| !self.is_score_withheld(&action_ref.account_did))
+            .filter_map(|action_ref| {
+                let tp_info = self.tp_infos.get(&action_ref.account_did)?;
+                let index = Self::find_action(&tp_info, &source, &identifier, action_ref)?;
+                tp_info.actions.get(index)
+            })
+            .collect()
+    }
+}
+
+impl CoretoTPLedger {
+    pub(crate) fn index_action(&mut self, account_did: &str, action: &CoretoTPAction, index: u64) {
+        let key = (action.source.clone(), action.identifier.clone());
+        let mut refs = self.action_index.get(&key).unwrap_or_default();
+
+        refs.push(CoretoTPActionRef {
+            account_did: account_did.to_string(),
+            action_type: action.action_type.clone(),
+            index,
+        });
+        self.action_index.insert(&key, &refs);
+    }
+
+    /// Applies `update` to every action the source wrote under `identifier`
+    /// and saves the ones it reports as changed. Returns how many changed.
+    pub(crate) fn update_actions(
+        &mut self,
+        source: &AccountId,
+        identifier: &String,
+        mut update: impl FnMut(&mut CoretoTPAction) -> bool,
+    ) -> u64 {
+        let refs = self.action_index.get(&(source.clone(), identifier.clone()));
+        require!(refs.is_some(), "Action not found.");
+
+        let mut updated = 0;
+        for action_ref in refs.unwrap().iter() {
+            let mut tp_info = self.tp_infos.get(&action_ref.account_did).unwrap();
+            let index = match Self::find_action(&tp_info, source, identifier, action_ref) {
+                Some(index) => index,
+                None => continue,
+            };
+
+            let mut action = tp_info.actions.get(index).unwrap();
+            if update(&mut action) {
+                tp_info.actions.replace(index, &action);
+                updated += 1;
+            }
+        }
+
+        updated
+    }
+
+    pub(crate) fn find_action(
+        tp_info: &CoretoTPStat,
+        source: &AccountId,
+        identifier: &String,
+        action_ref: &CoretoTPActionRef,
+    ) -> Option<u64> {
+        let matches = |action: &CoretoTPAction| {
+            action.source == *source
+                && action.identifier == *identifier
+                && action.action_type == action_ref.action_type
+        };
+
+        if tp_info.actions.get(action_ref.index).is_some_and(|action| matches(&action)) {
+            return Some(action_ref.index);
+        }
+
+        tp_info.actions
+            .iter()
+            .position(|action| matches(&action))
+            .map(|index| index as u64)
+    }
+}
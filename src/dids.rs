@@ -0,0 +1,251 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{Base58CryptoHash, Base64VecU8};
+use near_sdk::{bs58, env, near_bindgen, require, AccountId, PublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::relayer::verify_ed25519;
+use crate::*;
+
+/// Maximum length of a DID, in bytes.
+pub const MAX_DID_LENGTH: usize = 256;
+/// Maximum number of DIDs an account can claim.
+const MAX_CONTROLLED_DIDS: usize = 20;
+
+/// Multicodec prefix of an ed25519 public key in a `did:key` DID.
+const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Proof that the caller controls a DID. The signed message is the sha256
+/// hash of the Borsh-serialized `(ledger_account_id, account_did,
+/// claiming_account_id)` tuple.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+pub enum CoretoTPDidProof {
+    /// Signed with the registered public key of a source.
+    SourceSignature {
+        source: AccountId,
+        signature: Base64VecU8,
+    },
+    /// Signed with the ed25519 key a `did:key` DID encodes. Other DIDs
+    /// cannot be proven this way, as their keys are not on-chain.
+    DidKeySignature {
+        signature: Base64VecU8,
+    },
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Binds the DID to the calling account once `proof` is verified. The
+    /// attached deposit has to cover the storage of the claim; the rest is
+    /// refunded, and the claim's storage is refunded on release.
+    #[payable]
+    pub fn claim_did(&mut self, account_did: String, proof: CoretoTPDidProof) {
+        self.assert_not_paused(CoretoTPPauseCategory::DidRegistry);
+        let account_did = self.normalize_did(&account_did);
+        require!(
+            !account_did.starts_with("did:near:"),
+            "did:near DIDs are controlled by their account."
+        );
+        let did_key = self.hash_did(&account_did);
+        require!(
+            !self.did_controllers.contains_key(&did_key),
+            "DID already claimed."
+        );
+
+        let account_id = env::predecessor_account_id();
+        let message = did_claim_hash(&env::current_account_id(), &account_did, &account_id);
+        let verified = match proof {
+            CoretoTPDidProof::SourceSignature { source, signature } => self.sources
+                .get(&source)
+                .and_then(|source| source.public_key)
+                .is_some_and(|public_key| verify_ed25519(&public_key, &message, &signature.0)),
+            CoretoTPDidProof::DidKeySignature { signature } => did_key_public_key(&account_did)
+                .is_some_and(|public_key| verify_ed25519(&public_key, &message, &signature.0)),
+        };
+        require!(verified, "Invalid DID proof.");
+
+        let mut controlled_dids = self.controlled_dids.get(&account_id).unwrap_or_default();
+        require!(controlled_dids.len() < MAX_CONTROLLED_DIDS, "Too many DIDs claimed.");
+        let initial_storage_usage = env::storage_usage();
+
+        self.did_controllers.insert(&did_key, &account_id);
+
+        CoretoTPEvent::DidClaimed { account_did: &did_key, account_id: &account_id }.emit();
+
+        controlled_dids.push(did_key);
+        self.controlled_dids.insert(&account_id, &controlled_dids);
+
+        self.charge_attached_deposit(initial_storage_usage);
+    }
+
+    /// Unbinds a DID from the calling account.
+    pub fn release_did(&mut self, account_did: String) {
+        self.assert_not_paused(CoretoTPPauseCategory::DidRegistry);
+        let account_did = self.did_key(&account_did);
+
+        let account_id = env::predecessor_account_id();
+        require!(
+            self.did_controllers.get(&account_did) == Some(account_id.clone()),
+            "Not the DID controller."
+        );
+
+        self.unbind_did(&account_did);
+
+        CoretoTPEvent::DidReleased { account_did: &account_did, account_id: &account_id }.emit();
+    }
+
+    pub fn get_did_controller(&self, account_did: String) -> Option<AccountId> {
+        let account_did = self.normalize_did(&account_did);
+        if let Some(account_id) = self.did_controllers.get(&self.hash_did(&account_did)) {
+            return Some(account_id);
+        }
+
+        account_did
+            .strip_prefix("did:near:")
+            .and_then(|near_account| near_account.parse().ok())
+    }
+
+    /// Restricts the DID methods accepted by the ledger. `None` accepts any
+    /// method.
+    pub fn set_did_methods(&mut self, methods: Option<Vec<String>>) {
+        self.assert_owner();
+
+        self.did_methods = methods.map(|methods| {
+            methods.into_iter().map(|method| method.to_lowercase()).collect()
+        });
+
+        CoretoTPEvent::DidMethodsUpdated { methods: self.did_methods.as_ref() }.emit();
+    }
+
+    pub fn get_did_methods(&self) -> Option<Vec<String>> {
+        self.did_methods.clone()
+    }
+
+    /// The key the DID is stored under: the DID itself, or its salted hash
+    /// if the ledger was initialized with a DID salt.
+    pub fn get_did_key(&self, account_did: String) -> String {
+        self.did_key(&account_did)
+    }
+
+    /// Keys of the DIDs claimed by the account. Its implicit `did:near` DID
+    /// is not listed.
+    pub fn get_controlled_dids(&self, account_id: AccountId) -> Vec<String> {
+        self.controlled_dids.get(&account_id).unwrap_or_default()
+    }
+}
+
+impl CoretoTPLedger {
+    /// Removes the DID from the registry, if it was claimed, and refunds
+    /// the claim's storage to its controller.
+    pub(crate) fn unbind_did(&mut self, account_did: &String) {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = match self.did_controllers.remove(account_did) {
+            Some(account_id) => account_id,
+            None => return,
+        };
+
+        let mut controlled_dids = self.controlled_dids.get(&account_id).unwrap_or_default();
+        controlled_dids.retain(|controlled_did| controlled_did != account_did);
+        if controlled_dids.is_empty() {
+            self.controlled_dids.remove(&account_id);
+        } else {
+            self.controlled_dids.insert(&account_id, &controlled_dids);
+        }
+
+        self.refund_freed_storage(&account_id, initial_storage_usage);
+    }
+
+    /// Validates a DID and returns its normalised form, panicking if it is
+    /// invalid or its method is not allowed.
+    pub(crate) fn normalize_did(&self, account_did: &str) -> String {
+        require_ok(self.try_normalize_did(account_did))
+    }
+
+    pub(crate) fn try_normalize_did(&self, account_did: &str) -> Result<String, CoretoTPErrorCode> {
+        let account_did = normalize_did(account_did).ok_or(CoretoTPErrorCode::InvalidDid)?;
+        if !self.is_did_method_allowed(&account_did) {
+            return Err(CoretoTPErrorCode::DidMethodNotAllowed);
+        }
+
+        Ok(account_did)
+    }
+
+    /// Validates a DID and returns the key it is stored under.
+    pub(crate) fn did_key(&self, account_did: &str) -> String {
+        self.hash_did(&self.normalize_did(account_did))
+    }
+
+    pub(crate) fn try_did_key(&self, account_did: &str) -> Result<String, CoretoTPErrorCode> {
+        self.try_normalize_did(account_did).map(|account_did| self.hash_did(&account_did))
+    }
+
+    /// Hashes a normalised DID with the ledger's salt, if any.
+    pub(crate) fn hash_did(&self, account_did: &String) -> String {
+        match &self.did_salt {
+            Some(did_salt) => {
+                let salted = [did_salt.as_slice(), account_did.as_bytes()].concat();
+                String::from(&Base58CryptoHash::from(env::sha256_array(&salted)))
+            }
+            None => account_did.clone(),
+        }
+    }
+
+    fn is_did_method_allowed(&self, account_did: &str) -> bool {
+        let method = account_did.split(':').nth(1).unwrap_or_default();
+
+        self.did_methods
+            .as_ref()
+            .is_none_or(|methods| methods.iter().any(|allowed| allowed == method))
+    }
+
+    /// Whether `account_id` controls the normalised DID, either through a
+    /// verified claim or because it is the account a `did:near` DID names.
+    pub(crate) fn is_did_controller(&self, account_did: &str, account_id: &AccountId) -> bool {
+        self.get_did_controller(account_did.to_string()).as_ref() == Some(account_id)
+    }
+}
+
+/// Checks a DID against the `did:<method>:<method-specific-id>` syntax and
+/// lowercases its scheme and method. The method-specific id is kept as is;
+/// it may use any visible ASCII character except the `/`, `?` and `#` that
+/// start a DID URL.
+pub(crate) fn normalize_did(account_did: &str) -> Option<String> {
+    if account_did.len() > MAX_DID_LENGTH {
+        return None;
+    }
+
+    let mut parts = account_did.splitn(3, ':');
+    let scheme = parts.next()?;
+    let method = parts.next()?.to_lowercase();
+    let method_specific_id = parts.next()?;
+
+    let valid = scheme.eq_ignore_ascii_case("did")
+        && !method.is_empty()
+        && method.bytes().all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+        && !method_specific_id.is_empty()
+        && !method_specific_id.ends_with(':')
+        && method_specific_id
+            .bytes()
+            .all(|byte| byte.is_ascii_graphic() && !matches!(byte, b'/' | b'?' | b'#'));
+
+    if !valid {
+        return None;
+    }
+
+    Some(format!("did:{}:{}", method, method_specific_id))
+}
+
+pub(crate) fn did_claim_hash(ledger: &AccountId, account_did: &String, account_id: &AccountId) -> Vec<u8> {
+    let payload = (ledger, account_did, account_id).try_to_vec().unwrap();
+    env::sha256(&payload)
+}
+
+/// Decodes the ed25519 key of a `did:key:z<base58btc multicodec key>` DID.
+fn did_key_public_key(account_did: &str) -> Option<PublicKey> {
+    let encoded = account_did.strip_prefix("did:key:z")?;
+    let decoded = bs58::decode(encoded).into_vec().ok()?;
+    let key = decoded.strip_prefix(&ED25519_MULTICODEC)?;
+    if key.len() != 32 {
+        return None;
+    }
+
+    PublicKey::try_from([&[0], key].concat()).ok()
+}
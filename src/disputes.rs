@@ -0,0 +1,258 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{env, near_bindgen, require, AccountId};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Maximum number of disputes returned by one paginated view call.
+const MAX_DISPUTES_PAGE: u64 = 100;
+/// Maximum length of a dispute reason, in bytes.
+const MAX_DISPUTE_REASON_LENGTH: usize = 256;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPDisputeStatus {
+    Pending,
+    /// The action was retracted.
+    Upheld,
+    Rejected,
+    /// The action no longer exists, e.g. after its source was purged or
+    /// the DID redacted.
+    Void,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPDispute {
+    pub(crate) id: u64,
+    pub(crate) account_did: String,
+    pub(crate) source: AccountId,
+    pub(crate) identifier: String,
+    /// Cleared when the DID is redacted, as it may name the DID.
+    pub(crate) opened_by: Option<AccountId>,
+    pub(crate) reason: String,
+    /// Hash of evidence kept off-chain.
+    pub(crate) evidence_hash: Option<Base58CryptoHash>,
+    pub(crate) status: CoretoTPDisputeStatus,
+    /// Leaves the action out of the scores while the dispute is pending.
+    pub(crate) frozen: bool,
+    pub(crate) opened_date: String,
+    pub(crate) resolved_by: Option<AccountId>,
+    pub(crate) resolution: Option<String>,
+    pub(crate) resolved_date: Option<String>,
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Opens a dispute against an action recorded for a DID the caller
+    /// controls. Returns the dispute id.
+    pub fn open_dispute(
+        &mut self,
+        account_did: String,
+        source: AccountId,
+        identifier: String,
+        reason: String,
+        evidence_hash: Option<Base58CryptoHash>,
+    ) -> u64 {
+        self.assert_not_paused(CoretoTPPauseCategory::Disputes);
+        require!(reason.len() <= MAX_DISPUTE_REASON_LENGTH, "Reason too long.");
+        let account_did = self.normalize_did(&account_did);
+        require!(
+            self.is_did_controller(&account_did, &env::predecessor_account_id()),
+            "Not the DID controller."
+        );
+        let account_did = self.hash_did(&account_did);
+
+        let id = self.disputes.len();
+        let found = self.update_did_action(&account_did, &source, &identifier, |action| {
+            require!(action.retraction.is_none(), "Action is retracted.");
+            require!(action.dispute_id.is_none(), "Action already disputed.");
+
+            action.dispute_id = Some(id);
+        });
+        require!(found, "Action not found.");
+
+        self.disputes.push(&CoretoTPDispute {
+            id,
+            account_did: account_did.clone(),
+            source,
+            identifier,
+            opened_by: Some(env::predecessor_account_id()),
+            reason,
+            evidence_hash,
+            status: CoretoTPDisputeStatus::Pending,
+            frozen: false,
+            opened_date: env::block_timestamp().to_string(),
+            resolved_by: None,
+            resolution: None,
+            resolved_date: None,
+        });
+
+        let mut did_disputes = self.did_disputes.get(&account_did).unwrap_or_default();
+        did_disputes.push(id);
+        self.did_disputes.insert(&account_did, &did_disputes);
+
+        let dispute = self.disputes.get(id).unwrap();
+        CoretoTPEvent::DisputeOpened {
+            dispute_id: id,
+            account_did: &dispute.account_did,
+            source: &dispute.source,
+            identifier: &dispute.identifier,
+        }
+        .emit();
+
+        id
+    }
+
+    /// Freezes or unfreezes the disputed action in the scores while the
+    /// dispute is pending.
+    pub fn set_dispute_frozen(&mut self, dispute_id: u64, frozen: bool) {
+        self.assert_not_paused(CoretoTPPauseCategory::Disputes);
+        self.assert_any_role(&[CoretoTPRole::Moderator, CoretoTPRole::Arbiter]);
+
+        let mut dispute = self.pending_dispute(dispute_id);
+        dispute.frozen = frozen;
+        self.disputes.replace(dispute_id, &dispute);
+
+        CoretoTPEvent::DisputeFrozen { dispute_id, frozen }.emit();
+    }
+
+    /// Resolves a pending dispute. An upheld dispute retracts the action; a
+    /// dispute whose action no longer exists is voided.
+    pub fn resolve_dispute(&mut self, dispute_id: u64, upheld: bool, resolution: String) {
+        self.assert_not_paused(CoretoTPPauseCategory::Disputes);
+        self.assert_any_role(&[CoretoTPRole::Moderator, CoretoTPRole::Arbiter]);
+
+        let mut dispute = self.pending_dispute(dispute_id);
+        let retraction_reason = format!("Dispute {} upheld: {}", dispute_id, resolution);
+        let mut retracted = false;
+
+        let found = self.update_did_action(&dispute.account_did, &dispute.source, &dispute.identifier, |action| {
+            action.dispute_id = None;
+            if upheld && action.retraction.is_none() {
+                action.retraction = Some(CoretoTPRetraction {
+                    reason: retraction_reason.clone(),
+                    block_date: env::block_timestamp().to_string(),
+                });
+                retracted = true;
+            }
+        });
+
+        dispute.status = match (found, upheld) {
+            (false, _) => CoretoTPDisputeStatus::Void,
+            (true, true) => CoretoTPDisputeStatus::Upheld,
+            (true, false) => CoretoTPDisputeStatus::Rejected,
+        };
+        dispute.resolved_by = Some(env::predecessor_account_id());
+        dispute.resolution = Some(resolution);
+        dispute.resolved_date = Some(env::block_timestamp().to_string());
+        self.disputes.replace(dispute_id, &dispute);
+
+        if retracted {
+            CoretoTPEvent::ActionRetracted {
+                source: &dispute.source,
+                identifier: &dispute.identifier,
+                reason: &retraction_reason,
+            }
+            .emit();
+        }
+        if found {
+            CoretoTPEvent::DisputeResolved { dispute_id, upheld }.emit();
+        } else {
+            CoretoTPEvent::DisputeVoided { dispute_id }.emit();
+        }
+    }
+
+    pub fn get_dispute(&self, dispute_id: u64) -> Option<CoretoTPDispute> {
+        self.disputes.get(dispute_id)
+    }
+
+    pub fn get_disputes(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<CoretoTPDispute> {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(MAX_DISPUTES_PAGE).min(MAX_DISPUTES_PAGE);
+
+        (from_index..self.disputes.len().min(from_index.saturating_add(limit)))
+            .filter_map(|dispute_id| self.disputes.get(dispute_id))
+            .collect()
+    }
+
+    pub fn get_user_disputes(&self, account_did: String, from_index: Option<u64>, limit: Option<u64>) -> Vec<CoretoTPDispute> {
+        let account_did = self.did_key(&account_did);
+        let from_index = from_index.unwrap_or(0) as usize;
+        let limit = limit.unwrap_or(MAX_DISPUTES_PAGE).min(MAX_DISPUTES_PAGE) as usize;
+
+        self.did_disputes
+            .get(&account_did)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index)
+            .take(limit)
+            .filter_map(|dispute_id| self.disputes.get(dispute_id))
+            .collect()
+    }
+}
+
+impl CoretoTPLedger {
+    fn pending_dispute(&self, dispute_id: u64) -> CoretoTPDispute {
+        let dispute = self.disputes.get(dispute_id);
+        require!(dispute.is_some(), "Dispute not found.");
+
+        let dispute = dispute.unwrap();
+        require!(
+            dispute.status == CoretoTPDisputeStatus::Pending,
+            "Dispute already resolved."
+        );
+
+        dispute
+    }
+
+    /// Whether the action is left out of the scores by a pending frozen
+    /// dispute.
+    pub(crate) fn is_frozen(&self, action: &CoretoTPAction) -> bool {
+        action.dispute_id
+            .and_then(|dispute_id| self.disputes.get(dispute_id))
+            .is_some_and(|dispute| dispute.status == CoretoTPDisputeStatus::Pending && dispute.frozen)
+    }
+
+    /// Voids the dispute if it is still pending, as its action was removed.
+    pub(crate) fn void_dispute(&mut self, dispute_id: u64) {
+        let mut dispute = match self.disputes.get(dispute_id) {
+            Some(dispute) if dispute.status == CoretoTPDisputeStatus::Pending => dispute,
+            _ => return,
+        };
+
+        dispute.status = CoretoTPDisputeStatus::Void;
+        dispute.frozen = false;
+        dispute.resolved_date = Some(env::block_timestamp().to_string());
+        self.disputes.replace(dispute_id, &dispute);
+
+        CoretoTPEvent::DisputeVoided { dispute_id }.emit();
+    }
+
+    /// Applies `update` to the action the source wrote under `identifier`
+    /// in the DID's history. Returns whether the action was found.
+    fn update_did_action(
+        &mut self,
+        account_did: &String,
+        source: &AccountId,
+        identifier: &String,
+        update: impl FnOnce(&mut CoretoTPAction),
+    ) -> bool {
+        let action_ref = self.action_index
+            .get(&(source.clone(), identifier.clone()))
+            .and_then(|refs| refs.into_iter().find(|action_ref| action_ref.account_did == *account_did));
+        let mut tp_info = match (action_ref.as_ref(), self.tp_infos.get(account_did)) {
+            (Some(_), Some(tp_info)) => tp_info,
+            _ => return false,
+        };
+
+        let index = match Self::find_action(&tp_info, source, identifier, &action_ref.unwrap()) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let mut action = tp_info.actions.get(index).unwrap();
+        update(&mut action);
+        tp_info.actions.replace(index, &action);
+        true
+    }
+}
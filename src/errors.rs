@@ -0,0 +1,46 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::require;
+use serde::{Deserialize, Serialize};
+
+/// Why an action write was refused. Reported per item by batch writes that
+/// accept partial batches; every other write panics with `message()`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPErrorCode {
+    InvalidDid,
+    DidMethodNotAllowed,
+    DidOptedOut,
+    ActionTypeNotRegistered,
+    ActionTypeDeprecated,
+    ActionTypeRetired,
+    TrustOutOfBounds,
+    PerformanceOutOfBounds,
+    DuplicateAction,
+    InsufficientStorage,
+}
+
+impl CoretoTPErrorCode {
+    pub fn message(&self) -> &'static str {
+        match self {
+            CoretoTPErrorCode::InvalidDid => "Invalid DID.",
+            CoretoTPErrorCode::DidMethodNotAllowed => "DID method not allowed.",
+            CoretoTPErrorCode::DidOptedOut => "DID opted out of the source.",
+            CoretoTPErrorCode::ActionTypeNotRegistered => "Action type not registered.",
+            CoretoTPErrorCode::ActionTypeDeprecated => "Action type is deprecated.",
+            CoretoTPErrorCode::ActionTypeRetired => "Action type is retired.",
+            CoretoTPErrorCode::TrustOutOfBounds => "Trust out of bounds.",
+            CoretoTPErrorCode::PerformanceOutOfBounds => "Performance out of bounds.",
+            CoretoTPErrorCode::DuplicateAction => "Duplicate action.",
+            CoretoTPErrorCode::InsufficientStorage => "Insufficient storage balance.",
+        }
+    }
+}
+
+/// Returns the value, or panics with the error's message.
+pub(crate) fn require_ok<T>(result: Result<T, CoretoTPErrorCode>) -> T {
+    require!(
+        result.is_ok(),
+        result.as_ref().err().map_or("", |error| error.message())
+    );
+
+    result.unwrap()
+}
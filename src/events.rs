@@ -0,0 +1,181 @@
+use near_sdk::{env, AccountId, PublicKey};
+use serde::Serialize;
+
+use crate::*;
+
+/// NEP-297 standard name of the ledger's events.
+pub const EVENT_STANDARD: &str = "coreto_tp";
+/// Version of the event schema. Bumped when an event changes shape.
+pub const EVENT_VERSION: &str = "1.0.0";
+
+/// State changes logged as `EVENT_JSON:{"standard":"coreto_tp",
+/// "version":"1.0.0","event":"<name>","data":{...}}`. DIDs are given as
+/// their storage key.
+#[derive(Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub(crate) enum CoretoTPEvent<'a> {
+    ActionSaved {
+        source: &'a AccountId,
+        account_did: &'a str,
+        action_type: &'a str,
+        identifier: &'a str,
+        trust: f32,
+        performance: f32,
+    },
+    /// Counts only, as a list of DIDs could exceed the log limit; the
+    /// actions are paged with `get_batch_actions`.
+    BatchSaved {
+        source: &'a AccountId,
+        batch_id: u64,
+        actions_count: u64,
+        dids_count: u64,
+    },
+    BatchRetracted {
+        source: &'a AccountId,
+        batch_id: u64,
+        actions_count: u64,
+        reason: &'a str,
+    },
+    ActionRetracted {
+        source: &'a AccountId,
+        identifier: &'a str,
+        reason: &'a str,
+    },
+    ActionAmended {
+        source: &'a AccountId,
+        identifier: &'a str,
+        trust: f32,
+        performance: f32,
+        reason: &'a str,
+    },
+    SourceAdded {
+        source: &'a AccountId,
+        source_label: &'a str,
+    },
+    SourceRemoved {
+        source: &'a AccountId,
+        cleanup: CoretoTPSourceCleanup,
+    },
+    SourceCleanupCompleted {
+        source: &'a AccountId,
+    },
+    SignerSubmissionUpdated {
+        source: &'a AccountId,
+        allowed: bool,
+    },
+    SourcePublicKeyUpdated {
+        source: &'a AccountId,
+        public_key: Option<&'a PublicKey>,
+    },
+    SubmitterAdded {
+        source: &'a AccountId,
+        submitter: &'a AccountId,
+    },
+    SubmitterRemoved {
+        source: &'a AccountId,
+        submitter: &'a AccountId,
+    },
+    ActionTypeRegistered {
+        source: &'a AccountId,
+        action_type: &'a str,
+        weight: f32,
+    },
+    ActionTypeWeightUpdated {
+        source: &'a AccountId,
+        action_type: &'a str,
+        weight: f32,
+    },
+    ActionTypeStatusUpdated {
+        source: &'a AccountId,
+        action_type: &'a str,
+        status: CoretoTPActionTypeStatus,
+    },
+    OwnerProposed {
+        owner_id: &'a AccountId,
+    },
+    OwnershipTransferred {
+        owner_id: &'a AccountId,
+    },
+    RoleGranted {
+        account_id: &'a AccountId,
+        role: CoretoTPRole,
+    },
+    RoleRevoked {
+        account_id: &'a AccountId,
+        role: CoretoTPRole,
+    },
+    Paused {
+        categories: &'a [CoretoTPPauseCategory],
+    },
+    Unpaused {
+        categories: &'a [CoretoTPPauseCategory],
+    },
+    DidMethodsUpdated {
+        methods: Option<&'a Vec<String>>,
+    },
+    DidClaimed {
+        account_did: &'a str,
+        account_id: &'a AccountId,
+    },
+    DidReleased {
+        account_did: &'a str,
+        account_id: &'a AccountId,
+    },
+    DidMerged {
+        canonical_did: &'a str,
+        alias: &'a str,
+    },
+    DidUnmerged {
+        canonical_did: &'a str,
+        alias: &'a str,
+    },
+    ConsentUpdated {
+        account_did: &'a str,
+        excluded_sources: &'a [AccountId],
+        scores_withheld: bool,
+    },
+    DisputeOpened {
+        dispute_id: u64,
+        account_did: &'a str,
+        source: &'a AccountId,
+        identifier: &'a str,
+    },
+    DisputeFrozen {
+        dispute_id: u64,
+        frozen: bool,
+    },
+    DisputeResolved {
+        dispute_id: u64,
+        upheld: bool,
+    },
+    DisputeVoided {
+        dispute_id: u64,
+    },
+    RedactionRequested {
+        redaction_id: u64,
+    },
+    RedactionCompleted {
+        redaction_id: u64,
+        actions_count: u64,
+    },
+}
+
+#[derive(Serialize)]
+struct CoretoTPEventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a CoretoTPEvent<'a>,
+}
+
+impl CoretoTPEvent<'_> {
+    pub(crate) fn emit(&self) {
+        let log = CoretoTPEventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: self,
+        };
+
+        env::log_str(&format!("EVENT_JSON:{}", serde_json::to_string(&log).unwrap()));
+    }
+}
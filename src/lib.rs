@@ -1,22 +1,89 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, Vector, UnorderedSet};
+use near_sdk::collections::{LookupMap, Vector, UnorderedMap, UnorderedSet};
 use near_sdk::{
     env,
     require,
-    assert_self,
     near_bindgen,
     AccountId,
-    BorshStorageKey
+    BorshStorageKey,
+    CryptoHash,
+    Gas,
+    PublicKey
 };
 use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, HashSet};
+
+mod action_types;
+mod aliases;
+mod batches;
+mod consent;
+mod corrections;
+mod dids;
+mod disputes;
+mod errors;
+mod events;
+mod owner;
+mod pause;
+mod redactions;
+mod relayer;
+mod roles;
+mod storage;
+mod submitters;
+
+pub use crate::batches::{CoretoTPBatch, CoretoTPBatchResult, CoretoTPItemResult, CoretoTPItemStatus};
+pub use crate::action_types::{CoretoTPActionType, CoretoTPActionTypeRequestData, CoretoTPActionTypeStatus};
+pub use crate::consent::{CoretoTPConsent, CoretoTPScore};
+pub use crate::corrections::{CoretoTPActionRevision, CoretoTPRetraction};
+use crate::corrections::CoretoTPActionRef;
+pub use crate::dids::CoretoTPDidProof;
+pub use crate::errors::CoretoTPErrorCode;
+use crate::errors::require_ok;
+pub use crate::events::{EVENT_STANDARD, EVENT_VERSION};
+use crate::events::CoretoTPEvent;
+pub use crate::disputes::{CoretoTPDispute, CoretoTPDisputeStatus};
+pub use crate::pause::CoretoTPPauseCategory;
+pub use crate::redactions::{CoretoTPRedaction, CoretoTPRedactionMode};
+pub use crate::roles::CoretoTPRole;
+pub use crate::storage::{StorageBalance, StorageBalanceBounds};
+use crate::storage::CoretoTPStorageAccount;
+
+/// Gas kept aside when processing a source cleanup, so the call can persist
+/// its cursor before running out of gas.
+const CLEANUP_GAS_RESERVE: Gas = Gas(20_000_000_000_000);
+
+/// Gas kept aside when saving a batch, so the call can record the batch and
+/// return its results before running out of gas.
+const INGESTION_GAS_RESERVE: Gas = Gas(20_000_000_000_000);
 
 #[derive(BorshSerialize, BorshStorageKey)]
+#[allow(clippy::enum_variant_names)]
 enum StorageKeys {
     TPInfosKey,
     SourcesKey,
-    ActionsKey,
+    ActionsKey { account_hash: CryptoHash },
     SourceActionsKey,
-    SourceActionsSetKey,
+    SourceActionsSetKey { source_hash: CryptoHash },
+    SourceDidsKey,
+    SourceDidsSetKey { source_hash: CryptoHash },
+    SourceCleanupsKey,
+    RolesKey,
+    SubmittersKey,
+    SourceSubmittersKey,
+    SourceSubmittersSetKey { source_hash: CryptoHash },
+    ActionIndexKey,
+    DisputesKey,
+    DidDisputesKey,
+    DidControllersKey,
+    ControlledDidsKey,
+    DidAliasesKey,
+    CanonicalDidsKey,
+    RedactionsKey,
+    RedactionJobsKey,
+    ConsentsKey,
+    BatchesKey,
+    BatchActionsKey,
+    StorageAccountsKey,
+    ConsentHistoryKey { account_hash: CryptoHash },
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
@@ -39,27 +106,113 @@ pub struct CoretoTPAction {
 	source_label: String,
     source: AccountId,
     identifier: String,
+    retraction: Option<CoretoTPRetraction>,
+    revisions: Vec<CoretoTPActionRevision>,
+    /// Pending dispute against the action.
+    dispute_id: Option<u64>,
+    /// Batch the action was written in, if any.
+    batch_id: Option<u64>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPSource {
+	source_label: String,
+    /// Lets the source write through calls it signed but did not make
+    /// directly, e.g. relayed or forwarded by another contract.
+    allow_signer_submission: bool,
+    /// Key that signs batches submitted by relayers.
+    public_key: Option<PublicKey>,
+    /// Nonce of the last relayed batch.
+    nonce: u64,
+}
+
+/// Totals kept for a source whose actions were archived when it was removed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPActionsSummary {
+	source_label: String,
+    source: AccountId,
+    actions_count: u64,
+	trust: f32,
+	performance: f32,
+	archive_date: String,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct CoretoTPStat {
-    actions: Vector<CoretoTPAction>
+    actions: Vector<CoretoTPAction>,
+    archives: Vec<CoretoTPActionsSummary>,
 }
 
 impl CoretoTPStat {
-    fn default() -> Self {
+    fn new(account_did: &str) -> Self {
         Self {
-            actions: Vector::new(StorageKeys::ActionsKey),
+            actions: Vector::new(StorageKeys::ActionsKey {
+                account_hash: env::sha256_array(account_did.as_bytes()),
+            }),
+            archives: Vec::new(),
         }
     }
 }
 
+/// What happens to the actions of a source when it is removed.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPSourceCleanup {
+    /// Actions stay in every DID's history.
+    Keep,
+    /// Actions are deleted from every DID's history.
+    Purge,
+    /// Actions are replaced by a per-DID summary of the source.
+    Archive,
+}
+
+/// Progress of a purge or archive started by `remove_source`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPSourceCleanupJob {
+    mode: CoretoTPSourceCleanup,
+    source_label: String,
+    /// Number of the source's DIDs cleaned up so far.
+    cursor: u64,
+    total: u64,
+    /// Position of the next action to check in the DID's history.
+    action_cursor: u64,
+    /// Number of other sources' actions moved to the front of the DID's
+    /// history so far.
+    kept_count: u64,
+    /// Totals of the DID's actions removed so far, in `Archive` mode.
+    archived: Option<CoretoTPActionsSummary>,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct CoretoTPLedger {
     tp_infos: LookupMap<String, CoretoTPStat>,
-    sources: LookupMap<AccountId, String>,
-    source_actions: LookupMap<AccountId, UnorderedSet<String>>,
+    sources: LookupMap<AccountId, CoretoTPSource>,
+    source_actions: LookupMap<AccountId, UnorderedMap<String, CoretoTPActionType>>,
+    source_dids: LookupMap<AccountId, UnorderedSet<String>>,
+    source_cleanups: LookupMap<AccountId, CoretoTPSourceCleanupJob>,
+    owner_id: AccountId,
+    pending_owner_id: Option<AccountId>,
+    roles: LookupMap<AccountId, Vec<CoretoTPRole>>,
+    paused: Vec<CoretoTPPauseCategory>,
+    submitters: LookupMap<AccountId, AccountId>,
+    source_submitters: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    action_index: LookupMap<(AccountId, String), Vec<CoretoTPActionRef>>,
+    disputes: Vector<CoretoTPDispute>,
+    did_disputes: LookupMap<String, Vec<u64>>,
+    did_controllers: LookupMap<String, AccountId>,
+    controlled_dids: LookupMap<AccountId, Vec<String>>,
+    did_methods: Option<Vec<String>>,
+    /// Salt DIDs are hashed with before being used as storage keys.
+    did_salt: Option<Vec<u8>>,
+    did_aliases: LookupMap<String, String>,
+    canonical_dids: LookupMap<String, Vec<String>>,
+    redactions: Vector<CoretoTPRedaction>,
+    redaction_jobs: LookupMap<String, u64>,
+    consents: LookupMap<String, Vector<CoretoTPConsent>>,
+    batches: Vector<CoretoTPBatch>,
+    /// Identifier and location of every action a batch wrote.
+    batch_actions: LookupMap<u64, Vec<(String, CoretoTPActionRef)>>,
+    storage_accounts: LookupMap<AccountId, CoretoTPStorageAccount>,
 }
 
 impl Default for CoretoTPLedger {
@@ -68,31 +221,93 @@ impl Default for CoretoTPLedger {
             tp_infos: LookupMap::new(StorageKeys::TPInfosKey),
             sources: LookupMap::new(StorageKeys::SourcesKey),
             source_actions: LookupMap::new(StorageKeys::SourceActionsKey),
+            source_dids: LookupMap::new(StorageKeys::SourceDidsKey),
+            source_cleanups: LookupMap::new(StorageKeys::SourceCleanupsKey),
+            owner_id: env::current_account_id(),
+            pending_owner_id: None,
+            roles: LookupMap::new(StorageKeys::RolesKey),
+            paused: Vec::new(),
+            submitters: LookupMap::new(StorageKeys::SubmittersKey),
+            source_submitters: LookupMap::new(StorageKeys::SourceSubmittersKey),
+            action_index: LookupMap::new(StorageKeys::ActionIndexKey),
+            disputes: Vector::new(StorageKeys::DisputesKey),
+            did_disputes: LookupMap::new(StorageKeys::DidDisputesKey),
+            did_controllers: LookupMap::new(StorageKeys::DidControllersKey),
+            controlled_dids: LookupMap::new(StorageKeys::ControlledDidsKey),
+            did_methods: None,
+            did_salt: None,
+            did_aliases: LookupMap::new(StorageKeys::DidAliasesKey),
+            canonical_dids: LookupMap::new(StorageKeys::CanonicalDidsKey),
+            redactions: Vector::new(StorageKeys::RedactionsKey),
+            redaction_jobs: LookupMap::new(StorageKeys::RedactionJobsKey),
+            consents: LookupMap::new(StorageKeys::ConsentsKey),
+            batches: Vector::new(StorageKeys::BatchesKey),
+            batch_actions: LookupMap::new(StorageKeys::BatchActionsKey),
+            storage_accounts: LookupMap::new(StorageKeys::StorageAccountsKey),
         }
     }
 }
 
-
-#[near_bindgen]
 impl CoretoTPLedger {
-	pub fn save_actions_batch(
+    /// Resolves the source a write is made for. The calling account is
+    /// authorised directly; the signer only if its source opted in to
+    /// signer-based submission. Either can be the source or one of its
+    /// submitters.
+    fn resolve_source(&self) -> (AccountId, CoretoTPSource) {
+        if let Some(source_id) = self.source_of(&env::predecessor_account_id()) {
+            let source = self.sources.get(&source_id).unwrap();
+            return (source_id, source);
+        }
+
+        let source_id = self.source_of(&env::signer_account_id());
+        let source = source_id.as_ref().and_then(|source_id| self.sources.get(source_id));
+        require!(
+            source.as_ref().is_some_and(|source| source.allow_signer_submission),
+            "Invalid signer wallet."
+        );
+
+        (source_id.unwrap(), source.unwrap())
+    }
+
+    /// Writes the batch items. Without `partial`, the first invalid item
+    /// fails the whole batch; with it, invalid items are reported and
+    /// skipped. Duplicates are always skipped. Stops before the gas reserve
+    /// is reached; the result then gives the index to resume from.
+    fn save_batch(
         &mut self,
-        batch: Vec<CoretoTPActionRequestData>,
-    ) {
-		require!(
-			self.sources.contains_key(&env::signer_account_id()),
-			"Invalid signer wallet."
-		);
+        source_id: &AccountId,
+        source: &CoretoTPSource,
+        batch: &[CoretoTPActionRequestData],
+        partial: bool,
+    ) -> CoretoTPBatchResult {
+        let initial_storage_usage = env::storage_usage();
+        let action_types = self.source_action_types(source_id);
+        let batch_id = self.batches.len();
+
+        let mut results = Vec::with_capacity(batch.len());
+        let mut action_refs = Vec::new();
+        let mut account_dids = HashSet::new();
+        let mut next_index = None;
+        for (item_index, data) in batch.iter().enumerate() {
+            if env::used_gas() + INGESTION_GAS_RESERVE >= env::prepaid_gas() {
+                next_index = Some(item_index as u64);
+                break;
+            }
+
+            let account_did = match self.validate_batch_item(source_id, &action_types, data) {
+                Ok(account_did) => account_did,
+                Err(error) => {
+                    require!(partial, error.message());
+                    results.push(CoretoTPItemResult::rejected(error));
+                    continue;
+                }
+            };
 
-        let mut source_action = if self.source_actions.contains_key(&env::signer_account_id()) {
-            self.source_actions
-                .get(&env::signer_account_id())
-                .unwrap()
-        } else {
-            UnorderedSet::new(StorageKeys::SourceActionsSetKey)
-        };
+            if self.is_duplicate_action(source_id, &account_did, &data.action_type, &data.identifier) {
+                results.push(CoretoTPItemResult::duplicate());
+                continue;
+            }
 
-        for data in batch.iter() {
             let action = CoretoTPAction {
                 trust: data.trust,
                 performance: data.performance,
@@ -100,20 +315,259 @@ impl CoretoTPLedger {
                 action_date: data.action_date.clone(),
                 identifier: data.identifier.clone(),
                 block_date: env::block_timestamp().to_string(),
-                source_label: self.sources.get(&env::signer_account_id()).unwrap(),
-                source: env::signer_account_id(),
+                source_label: source.source_label.clone(),
+                source: source_id.clone(),
+                retraction: None,
+                revisions: Vec::new(),
+                dispute_id: None,
+                batch_id: Some(batch_id),
             };
 
-            let mut tp_info = self.tp_infos.get(&data.account_did)
-                .unwrap_or(CoretoTPStat::default());
+            let index = self.record_action(&account_did, &action);
+            results.push(CoretoTPItemResult::accepted());
+            action_refs.push((action.identifier, CoretoTPActionRef {
+                account_did: account_did.clone(),
+                action_type: action.action_type,
+                index,
+            }));
+            account_dids.insert(account_did);
+        }
+
+        let processed = &batch[..next_index.map_or(batch.len(), |next_index| next_index as usize)];
+        self.batches.push(&CoretoTPBatch {
+            id: batch_id,
+            source: source_id.clone(),
+            items_count: processed.len() as u64,
+            actions_count: action_refs.len() as u64,
+            content_hash: env::sha256_array(&processed.try_to_vec().unwrap()).into(),
+            block_height: env::block_height(),
+            block_date: env::block_timestamp().to_string(),
+        });
+
+        CoretoTPEvent::BatchSaved {
+            source: source_id,
+            batch_id,
+            actions_count: action_refs.len() as u64,
+            dids_count: account_dids.len() as u64,
+        }
+        .emit();
+
+        self.batch_actions.insert(&batch_id, &action_refs);
+        self.charge_storage(source_id, initial_storage_usage);
+
+        CoretoTPBatchResult { batch_id, results, next_index }
+    }
+
+    /// Appends the action to the DID's history. Returns its position.
+    fn record_action(&mut self, account_did: &String, action: &CoretoTPAction) -> u64 {
+        self.assert_source_consented(account_did, &action.source);
+
+        let mut tp_info = self.tp_infos.get(account_did)
+            .unwrap_or_else(|| CoretoTPStat::new(account_did));
+
+        let index = tp_info.actions.len();
+        tp_info.actions.push(action);
+        self.tp_infos.insert(account_did, &tp_info);
+        self.index_action(account_did, action, index);
+
+        let mut source_dids = self.source_dids
+            .get(&action.source)
+            .unwrap_or_else(|| UnorderedSet::new(StorageKeys::SourceDidsSetKey {
+                source_hash: env::sha256_array(action.source.as_bytes()),
+            }));
+
+        if source_dids.insert(account_did) {
+            self.source_dids.insert(&action.source, &source_dids);
+        }
+
+        index
+    }
+
+    /// Histories of the DID and of the DIDs merged with it. Fails if the
+    /// DID opted out of score exposure, as its values give its scores away.
+    fn user_stats(&self, account_did: &str) -> Vec<CoretoTPStat> {
+        let account_did = self.did_key(account_did);
+        let stats = self.merged_stats(&account_did);
+        require!(!stats.is_empty(), "AccountDID not found.");
+        require!(!self.is_score_withheld(&account_did), "Scores withheld.");
+
+        stats
+    }
+
+    /// Sums `value` over the actions of the DID and the DIDs merged with it
+    /// from the source label, weighted by the weight of each action type.
+    /// Retracted actions, actions frozen by a dispute and retired types are
+    /// left out. Withheld if the DID opted out of score exposure.
+    fn user_score(&self, source_label: &String, account_did: &String, value: fn(&CoretoTPAction) -> f32) -> CoretoTPScore {
+        if self.is_score_withheld(account_did) {
+            return CoretoTPScore::Withheld;
+        }
+
+        let mut weights: HashMap<(AccountId, String), f32> = HashMap::new();
+
+        let score = self.merged_stats(account_did)
+            .iter()
+            .flat_map(|tp_info| tp_info.actions.iter())
+            .filter(|action| action.source_label == *source_label && action.retraction.is_none() && !self.is_frozen(action))
+            .map(|action| {
+                let weight = *weights
+                    .entry((action.source.clone(), action.action_type.clone()))
+                    .or_insert_with(|| {
+                        self.source_actions
+                            .get(&action.source)
+                            .and_then(|action_types| action_types.get(&action.action_type))
+                            .map_or(1.0, |action_type| match action_type.status {
+                                CoretoTPActionTypeStatus::Retired => 0.0,
+                                _ => action_type.weight,
+                            })
+                    });
+
+                weight * value(&action)
+            })
+            .sum();
+
+        CoretoTPScore::Value(score)
+    }
+
+    /// Purges or archives the source's actions DID by DID, then removes its
+    /// action types one by one, until the work is done or the gas reserve is
+    /// reached. Each DID is dropped from the source's DIDs once it is done.
+    /// The storage freed is credited to the source. Returns the number of
+    /// DIDs and action types left.
+    fn process_source_cleanup(&mut self, source: &AccountId) -> u64 {
+        let initial_storage_usage = env::storage_usage();
+        let mut job = self.source_cleanups.get(source).unwrap();
+        let has_gas = || env::used_gas() + CLEANUP_GAS_RESERVE < env::prepaid_gas();
+
+        if let Some(mut source_dids) = self.source_dids.get(source) {
+            while let Some(account_did) = source_dids.as_vector().get(source_dids.len().saturating_sub(1)) {
+                if !self.cleanup_did_actions(&account_did, source, &mut job) {
+                    break;
+                }
+
+                source_dids.remove(&account_did);
+                job.cursor += 1;
+                job.action_cursor = 0;
+                job.kept_count = 0;
+            }
+
+            if !source_dids.is_empty() {
+                self.source_dids.insert(source, &source_dids);
+                self.source_cleanups.insert(source, &job);
+                self.credit_storage(source, initial_storage_usage);
+                let action_types = self.source_actions.get(source).map_or(0, |action_types| action_types.len());
+                return source_dids.len() + action_types;
+            }
+            self.source_dids.remove(source);
+        }
+
+        if let Some(mut action_types) = self.source_actions.get(source) {
+            while let Some(action_type) = action_types.keys_as_vector().get(action_types.len().saturating_sub(1)) {
+                if !has_gas() {
+                    self.source_actions.insert(source, &action_types);
+                    self.source_cleanups.insert(source, &job);
+                    self.credit_storage(source, initial_storage_usage);
+                    return action_types.len();
+                }
+                action_types.remove(&action_type);
+            }
+            self.source_actions.remove(source);
+        }
+        self.source_cleanups.remove(source);
+        self.credit_storage(source, initial_storage_usage);
+
+        CoretoTPEvent::SourceCleanupCompleted { source }.emit();
+        0
+    }
+
+    /// Removes the source's actions from the DID's history one action at a
+    /// time: they are first swapped behind the other sources' actions, which
+    /// keep their order, then popped. Returns whether the DID is done; if
+    /// not, the gas reserve was reached and the job holds the position.
+    fn cleanup_did_actions(&mut self, account_did: &String, source: &AccountId, job: &mut CoretoTPSourceCleanupJob) -> bool {
+        let mut tp_info = match self.tp_infos.get(account_did) {
+            Some(tp_info) => tp_info,
+            None => return true,
+        };
+        let has_gas = || env::used_gas() + CLEANUP_GAS_RESERVE < env::prepaid_gas();
+
+        while job.action_cursor < tp_info.actions.len() {
+            if !has_gas() {
+                return false;
+            }
+
+            let action = tp_info.actions.get(job.action_cursor).unwrap();
+            if action.source != *source {
+                if job.kept_count < job.action_cursor {
+                    let removed = tp_info.actions.replace(job.kept_count, &action);
+                    tp_info.actions.replace(job.action_cursor, &removed);
+                }
+                job.kept_count += 1;
+            }
+            job.action_cursor += 1;
+        }
 
-            tp_info.actions.push(&action);
-            source_action.insert(&action.action_type);
+        while tp_info.actions.len() > job.kept_count {
+            if !has_gas() {
+                self.tp_infos.insert(account_did, &tp_info);
+                return false;
+            }
+
+            let action = tp_info.actions.pop().unwrap();
+            self.action_index.remove(&(action.source.clone(), action.identifier.clone()));
+            if let Some(dispute_id) = action.dispute_id {
+                self.void_dispute(dispute_id);
+            }
+            if job.mode == CoretoTPSourceCleanup::Archive && action.retraction.is_none() {
+                let archived = job.archived.get_or_insert_with(|| CoretoTPActionsSummary {
+                    source_label: job.source_label.clone(),
+                    source: source.clone(),
+                    actions_count: 0,
+                    trust: 0.0,
+                    performance: 0.0,
+                    archive_date: String::new(),
+                });
+                archived.actions_count += 1;
+                archived.trust += action.trust;
+                archived.performance += action.performance;
+            }
+        }
 
-            self.tp_infos.insert(&data.account_did, &tp_info);
+        if let Some(mut archived) = job.archived.take() {
+            archived.archive_date = env::block_timestamp().to_string();
+            tp_info.archives.push(archived);
         }
+        self.tp_infos.insert(account_did, &tp_info);
+
+        true
+    }
+}
+
+
+#[near_bindgen]
+impl CoretoTPLedger {
+	/// Saves the batch and returns its id with a result per item. With
+	/// `partial`, invalid items are rejected individually instead of failing
+	/// the whole batch.
+	pub fn save_actions_batch(
+        &mut self,
+        batch: Vec<CoretoTPActionRequestData>,
+        partial: Option<bool>,
+    ) -> CoretoTPBatchResult {
+		self.assert_not_paused(CoretoTPPauseCategory::Ingestion);
+		let (source_id, source) = self.resolve_source();
+
+        self.save_batch(&source_id, &source, &batch, partial.unwrap_or(false))
+	}
 
-        self.source_actions.insert(&env::signer_account_id(), &source_action);
+	/// `save_actions_batch` with Borsh-serialized arguments, which are
+	/// cheaper to decode than JSON. The result is still returned as JSON.
+	pub fn save_actions_batch_borsh(
+        &mut self,
+        #[serializer(borsh)] batch: Vec<CoretoTPActionRequestData>,
+        #[serializer(borsh)] partial: Option<bool>,
+    ) -> CoretoTPBatchResult {
+		self.save_actions_batch(batch, partial)
 	}
 
 	pub fn save_action(
@@ -125,10 +579,10 @@ impl CoretoTPLedger {
         action_date: String,
         identifier: String,
     ) {
-		require!(
-			self.sources.contains_key(&env::signer_account_id()),
-			"Invalid signer wallet."
-		);
+		self.assert_not_paused(CoretoTPPauseCategory::Ingestion);
+		let (source_id, source) = self.resolve_source();
+
+        Self::assert_valid_action(&self.source_action_types(&source_id), &action_type, trust, performance);
 
         let action = CoretoTPAction {
             trust,
@@ -137,116 +591,210 @@ impl CoretoTPLedger {
             action_date,
             identifier,
             block_date: env::block_timestamp().to_string(),
-            source_label: self.sources.get(&env::signer_account_id()).unwrap(),
-            source: env::signer_account_id(),
-        };
-
-        let mut tp_info = if self.tp_infos.contains_key(&account_did) {
-            self.tp_infos.get(&account_did).unwrap()
-        } else {
-            CoretoTPStat::default()
-        };
-
-        tp_info.actions.push(&action);
-        self.tp_infos.insert(&account_did, &tp_info);
-
-        let mut source_action = if self.source_actions.contains_key(&env::signer_account_id()) {
-            self.source_actions
-                .get(&env::signer_account_id())
-                .unwrap()
-        } else {
-            UnorderedSet::new(
-                StorageKeys::SourceActionsSetKey
-            )
+            source_label: source.source_label,
+            source: source_id.clone(),
+            retraction: None,
+            revisions: Vec::new(),
+            dispute_id: None,
+            batch_id: None,
         };
 
-        source_action.insert(&action.action_type);
-        self.source_actions.insert(&env::signer_account_id(), &source_action);
+        let initial_storage_usage = env::storage_usage();
+        let account_did = self.did_key(&account_did);
+        require!(
+            !self.is_duplicate_action(&source_id, &account_did, &action.action_type, &action.identifier),
+            CoretoTPErrorCode::DuplicateAction.message()
+        );
+        self.record_action(&account_did, &action);
+        self.charge_storage(&source_id, initial_storage_usage);
+
+        CoretoTPEvent::ActionSaved {
+            source: &source_id,
+            account_did: &account_did,
+            action_type: &action.action_type,
+            identifier: &action.identifier,
+            trust: action.trust,
+            performance: action.performance,
+        }
+        .emit();
 	}
 
-	pub fn get_user_actions(&self, source_label: String, account_did: String) -> Vec<CoretoTPAction> {
-		require!(self.tp_infos.contains_key(&account_did), "AccountDID not found.");
-
-		let infos : CoretoTPStat = self.tp_infos.get(&account_did).unwrap();
+	pub fn get_user_actions(&self, source_label: String, account_did: String, include_retracted: Option<bool>) -> Vec<CoretoTPAction> {
+		let stats = self.user_stats(&account_did);
 
-        return infos.actions
-            .to_vec()
-            .into_iter()
+        stats
+            .iter()
+            .flat_map(|infos| infos.actions.iter())
             .filter(|action| action.source_label == source_label)
-            .collect();
+            .filter(|action| include_retracted.unwrap_or(false) || action.retraction.is_none())
+            .collect()
 	}
 
-    pub fn get_user_trust_actions(&self, source_label: String, account_did: String) -> Vec<CoretoTPAction> {
-		require!(self.tp_infos.contains_key(&account_did), "AccountDID not found.");
+    pub fn get_user_trust_actions(&self, source_label: String, account_did: String, include_retracted: Option<bool>) -> Vec<CoretoTPAction> {
+		let stats = self.user_stats(&account_did);
 
-		let infos : CoretoTPStat = self.tp_infos.get(&account_did).unwrap();
-
-        return infos.actions
-            .to_vec()
-            .into_iter()
+        stats
+            .iter()
+            .flat_map(|infos| infos.actions.iter())
             .filter(|action| action.source_label == source_label && action.trust > 0.0)
-            .collect();
+            .filter(|action| include_retracted.unwrap_or(false) || action.retraction.is_none())
+            .collect()
 	}
 
-    pub fn get_user_performance_actions(&self, source_label: String, account_did: String) -> Vec<CoretoTPAction> {
-		require!(self.tp_infos.contains_key(&account_did), "AccountDID not found.");
-
-		let infos : CoretoTPStat = self.tp_infos.get(&account_did).unwrap();
+    pub fn get_user_performance_actions(&self, source_label: String, account_did: String, include_retracted: Option<bool>) -> Vec<CoretoTPAction> {
+		let stats = self.user_stats(&account_did);
 
-        return infos.actions
-            .to_vec()
-            .into_iter()
+        stats
+            .iter()
+            .flat_map(|infos| infos.actions.iter())
             .filter(|action| action.source_label == source_label && action.performance > 0.0)
-            .collect();
+            .filter(|action| include_retracted.unwrap_or(false) || action.retraction.is_none())
+            .collect()
 	}
 
     pub fn get_source_action_types(&self, source: AccountId) -> Vec<String> {
 		require!(
-			self.source_actions.contains_key(&source),
+			self.sources.contains_key(&source) && self.source_actions.contains_key(&source),
 			"Source not found."
 		);
 
-        return self.source_actions.get(&source).unwrap().to_vec();
+        self.source_actions.get(&source).unwrap().keys_as_vector().to_vec()
 	}
 
-    pub fn get_user_trust(&self, source_label: String, account_did: String) -> f32 {
-        env::log_str(&format!("{} {}", source_label, account_did.to_string()).to_string());
-		return 0.0;
+    pub fn get_user_archived_actions(&self, source_label: String, account_did: String) -> Vec<CoretoTPActionsSummary> {
+		let stats = self.user_stats(&account_did);
+
+        stats
+            .into_iter()
+            .flat_map(|infos| infos.archives)
+            .filter(|summary| summary.source_label == source_label)
+            .collect()
+	}
+
+    pub fn get_source(&self, source: AccountId) -> Option<CoretoTPSource> {
+        self.sources.get(&source)
+	}
+
+    pub fn get_source_cleanup(&self, source: AccountId) -> Option<CoretoTPSourceCleanupJob> {
+        self.source_cleanups.get(&source)
+	}
+
+    pub fn get_user_trust(&self, source_label: String, account_did: String) -> CoretoTPScore {
+        self.user_score(&source_label, &self.did_key(&account_did), |action| action.trust)
 	}
 
-    pub fn get_user_performance(&self, source_label: String, account_did: String) -> f32 {
-        env::log_str(&format!("{} {}", source_label, account_did.to_string()).to_string());
-		return 0.0;
+    pub fn get_user_performance(&self, source_label: String, account_did: String) -> CoretoTPScore {
+        self.user_score(&source_label, &self.did_key(&account_did), |action| action.performance)
 	}
 
 	pub fn add_source(&mut self, source: AccountId, source_label: String) {
-		assert_self();
+		self.assert_role(CoretoTPRole::SourceManager);
+		self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
 		require!(
 			!self.sources.contains_key(&source),
 			"Source already exists."
 		);
+		require!(
+			!self.source_cleanups.contains_key(&source),
+			"Source cleanup in progress."
+		);
+		require!(
+			!self.submitters.contains_key(&source),
+			"Source is a submitter."
+		);
+
+		self.sources.insert(&source, &CoretoTPSource {
+			source_label: source_label.clone(),
+			allow_signer_submission: false,
+			public_key: None,
+			nonce: 0,
+		});
+
+		CoretoTPEvent::SourceAdded { source: &source, source_label: &source_label }.emit();
+	}
+
+	/// Opts the calling source in or out of signer-based submission, where a
+	/// write signed by the source reaches the contract through another account.
+	pub fn set_signer_submission(&mut self, allowed: bool) {
+		self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
+		let source_id = env::predecessor_account_id();
+		require!(
+			self.sources.contains_key(&source_id),
+			"Source not found."
+		);
+
+		let initial_storage_usage = env::storage_usage();
+		let mut source = self.sources.get(&source_id).unwrap();
+		source.allow_signer_submission = allowed;
+		self.sources.insert(&source_id, &source);
+		self.charge_storage(&source_id, initial_storage_usage);
 
-		self.sources.insert(&source, &source_label);
+		CoretoTPEvent::SignerSubmissionUpdated { source: &source_id, allowed }.emit();
 	}
 
-	pub fn remove_source(&mut self, source: AccountId) {
-		assert_self();
+	/// Removes the source. `cleanup` decides what happens to its actions and
+	/// defaults to `Keep`; a purge or archive that does not fit in the attached
+	/// gas is resumed with `continue_source_cleanup`.
+	pub fn remove_source(&mut self, source: AccountId, cleanup: Option<CoretoTPSourceCleanup>) {
+		self.assert_role(CoretoTPRole::SourceManager);
+		self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
 		require!(
 			self.sources.contains_key(&source),
 			"Source not found."
 		);
 
-		self.sources.remove(&source);
+		let source_label = self.sources.remove(&source).unwrap().source_label;
+		self.remove_all_submitters(&source);
+
+		let mode = cleanup.unwrap_or(CoretoTPSourceCleanup::Keep);
+		CoretoTPEvent::SourceRemoved { source: &source, cleanup: mode }.emit();
+
+		// Kept actions are still scored with the weight and status of their
+		// type, so the action types stay registered.
+		if mode == CoretoTPSourceCleanup::Keep {
+			return;
+		}
+
+		let total = self.source_dids.get(&source).map_or(0, |source_dids| source_dids.len());
+
+		self.source_cleanups.insert(&source, &CoretoTPSourceCleanupJob {
+			mode,
+			source_label,
+			cursor: 0,
+			total,
+			action_cursor: 0,
+			kept_count: 0,
+			archived: None,
+		});
+		self.process_source_cleanup(&source);
+	}
+
+	/// Resumes a pending purge or archive. Returns the number of DIDs and
+	/// action types left.
+	pub fn continue_source_cleanup(&mut self, source: AccountId) -> u64 {
+		self.assert_role(CoretoTPRole::SourceManager);
+		self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
+		require!(
+			self.source_cleanups.contains_key(&source),
+			"Source cleanup not found."
+		);
+
+		self.process_source_cleanup(&source)
 	}
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod tests {
-    use near_sdk::test_utils::{accounts, VMContextBuilder};
-    use near_sdk::{testing_env};
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::{testing_env, Balance};
 
     use super::*;
+    use ed25519_dalek::Signer;
+    use near_sdk::json_types::{Base58CryptoHash, Base64VecU8};
+
+    const STORAGE_DEPOSIT: Balance = 10_000_000_000_000_000_000_000_000;
+    const CLAIM_DEPOSIT: Balance = 10_000_000_000_000_000_000_000;
 
     // Allows for modifying the environment of the mocked blockchain
     fn get_context(predecessor_account_id: AccountId) -> VMContextBuilder {
@@ -258,6 +806,36 @@ mod tests {
         builder
     }
 
+    /// Funds the source's storage, keeping the caller of the current
+    /// context.
+    fn deposit_storage(contract: &mut CoretoTPLedger, source: AccountId) {
+        let predecessor_account_id = env::predecessor_account_id();
+        testing_env!(get_context(predecessor_account_id.clone()).attached_deposit(STORAGE_DEPOSIT).build());
+        contract.storage_deposit(Some(source), None);
+        testing_env!(get_context(predecessor_account_id).build());
+    }
+
+    fn set_mocked_consent(contract: &mut CoretoTPLedger, account_did: String, excluded_sources: Vec<AccountId>, scores_withheld: bool) {
+        let predecessor_account_id = env::predecessor_account_id();
+        testing_env!(get_context(predecessor_account_id).attached_deposit(CLAIM_DEPOSIT).build());
+        contract.set_consent(account_did, excluded_sources, scores_withheld);
+    }
+
+    fn register_mocked_action_type(contract: &mut CoretoTPLedger, source: AccountId, action_type: &str) {
+        contract.register_action_type(
+            source,
+            CoretoTPActionTypeRequestData {
+                action_type: action_type.to_string(),
+                description: format!("{} action", action_type),
+                min_trust: -100.0,
+                max_trust: 100.0,
+                min_performance: -100.0,
+                max_performance: 100.0,
+                default_weight: 1.0,
+            },
+        );
+    }
+
     fn get_contact_with_mocked_source_and_action(source_label: String, action: String) -> CoretoTPLedger {
         let mut context = get_context(accounts(0));
         // Initialize the mocked blockchain
@@ -269,10 +847,12 @@ mod tests {
             accounts(1),
             source_label.to_string(),
         );
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), &action);
 
         testing_env!(
             context
-                .signer_account_id(accounts(1))
+                .predecessor_account_id(accounts(1))
                 .build()
         );
 
@@ -285,7 +865,7 @@ mod tests {
             "123".to_string(),
         );
 
-        return contract;
+        contract
     }
 
     #[test]
@@ -300,7 +880,7 @@ mod tests {
             "coreto".to_string(),
             "did:mock:accounts(1)".to_string(),
         );
-        assert_eq!(trust, 0.0);
+        assert_eq!(trust, CoretoTPScore::Value(0.0));
     }
 
     #[test]
@@ -314,7 +894,7 @@ mod tests {
             "coreto".to_string(),
             "did:mock:accounts(1)".to_string(),
         );
-        assert_eq!(trust, 0.0);
+        assert_eq!(trust, CoretoTPScore::Value(0.0));
     }
 
     #[test]
@@ -347,10 +927,12 @@ mod tests {
             accounts(1),
             "coreto_website".to_string(),
         );
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
 
         testing_env!(
             context
-                .signer_account_id(accounts(1))
+                .predecessor_account_id(accounts(1))
                 .build()
         );
 
@@ -376,10 +958,13 @@ mod tests {
             accounts(1),
             "coreto_website".to_string(),
         );
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+        register_mocked_action_type(&mut contract, accounts(1), "reaction2");
 
         testing_env!(
             context
-                .signer_account_id(accounts(1))
+                .predecessor_account_id(accounts(1))
                 .build()
         );
 
@@ -412,16 +997,19 @@ mod tests {
 
         contract.save_actions_batch(
             actions_batch,
+            None,
         );
 
         let mut account_3_actions: Vec<CoretoTPAction> = contract.get_user_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(3)".to_string(),
+            None,
         );
 
         let mut account_4_actions: Vec<CoretoTPAction> = contract.get_user_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(4)".to_string(),
+            None,
         );
 
         assert_eq!(account_3_actions.len(), 2);
@@ -458,16 +1046,19 @@ mod tests {
 
         contract.save_actions_batch(
             actions_batch2,
+            None,
         );
 
         account_3_actions = contract.get_user_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(3)".to_string(),
+            None,
         );
 
         account_4_actions = contract.get_user_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(4)".to_string(),
+            None,
         );
 
         assert_eq!(account_3_actions.len(), 4);
@@ -504,6 +1095,8 @@ mod tests {
             "reaction".to_string(),
         );
 
+        register_mocked_action_type(&mut contract, accounts(1), "article");
+
         contract.save_action(
             "did:mock:accounts(1)".to_string(),
             10.0,
@@ -529,6 +1122,7 @@ mod tests {
         let trust_actions = contract.get_user_trust_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(2)".to_string(),
+            None,
         );
 
         assert_eq!(trust_actions.len(), 1);
@@ -547,6 +1141,7 @@ mod tests {
         contract.get_user_trust_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(3)".to_string(),
+            None,
         );
     }
 
@@ -560,6 +1155,7 @@ mod tests {
         let performance_actions = contract.get_user_performance_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(2)".to_string(),
+            None,
         );
         assert_eq!(performance_actions.len(), 1);
         assert_eq!(performance_actions[0].action_type, "reaction-performance");
@@ -577,6 +1173,7 @@ mod tests {
         contract.get_user_performance_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(3)".to_string(),
+            None,
         );
     }
 
@@ -592,6 +1189,7 @@ mod tests {
         contract.get_user_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(1)".to_string(),
+            None,
         );
     }
 
@@ -605,13 +1203,14 @@ mod tests {
         let actions: Vec<CoretoTPAction> = contract.get_user_actions(
             "coreto_website".to_string(),
             "did:mock:accounts(2)".to_string(),
+            None,
         );
 
         assert_eq!(actions.len(), 1);
     }
 
     #[test]
-    #[should_panic(expected = r#"Method is private"#)]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
     fn add_source_not_owner() {
         let context = get_context(accounts(1));
         // Initialize the mocked blockchain
@@ -657,14 +1256,14 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = r#"Method is private"#)]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
     fn remove_source_not_owner() {
         let context = get_context(accounts(1));
         // Initialize the mocked blockchain
         testing_env!(context.build());
 
         let mut contract = CoretoTPLedger::default();
-        contract.remove_source(accounts(1));
+        contract.remove_source(accounts(1), None);
     }
 
     #[test]
@@ -675,7 +1274,7 @@ mod tests {
         testing_env!(context.build());
 
         let mut contract = CoretoTPLedger::default();
-        contract.remove_source(accounts(1));
+        contract.remove_source(accounts(1), None);
     }
 
     #[test]
@@ -690,6 +1289,2388 @@ mod tests {
             "coreto_website".to_string(),
         );
 
-        contract.remove_source(accounts(1));
+        contract.remove_source(accounts(1), None);
+    }
+
+    fn save_mocked_action(contract: &mut CoretoTPLedger, account_did: &str, action: &str, identifier: &str) {
+        contract.save_action(
+            account_did.to_string(),
+            10.0,
+            5.0,
+            action.to_string(),
+            "1640995200".to_string(),
+            identifier.to_string(),
+        );
+    }
+
+    fn get_contract_with_two_sources() -> CoretoTPLedger {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        contract.add_source(accounts(2), "coreto_app".to_string());
+        deposit_storage(&mut contract, accounts(2));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+        register_mocked_action_type(&mut contract, accounts(2), "article");
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(3)", "reaction", "1");
+        save_mocked_action(&mut contract, "did:mock:accounts(3)", "reaction", "2");
+        save_mocked_action(&mut contract, "did:mock:accounts(4)", "reaction", "3");
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(3)", "article", "1");
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        contract
+    }
+
+    #[test]
+    fn remove_source_keep_actions() {
+        let mut contract = get_contract_with_two_sources();
+
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Keep));
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 2);
+        assert!(contract.get_source_cleanup(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn remove_source_purge_actions() {
+        let mut contract = get_contract_with_two_sources();
+
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Purge));
+
+        let account_3_actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+            None,
+        );
+        let account_4_actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(4)".to_string(),
+            None,
+        );
+        let other_source_actions = contract.get_user_actions(
+            "coreto_app".to_string(),
+            "did:mock:accounts(3)".to_string(),
+            None,
+        );
+
+        assert_eq!(account_3_actions.len(), 0);
+        assert_eq!(account_4_actions.len(), 0);
+        assert_eq!(other_source_actions.len(), 1);
+        assert_eq!(other_source_actions[0].action_type, "article");
+        assert!(contract.get_source_cleanup(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn remove_source_archive_actions() {
+        let mut contract = get_contract_with_two_sources();
+
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Archive));
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+            None,
+        );
+        let archives = contract.get_user_archived_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+        );
+
+        assert_eq!(actions.len(), 0);
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].actions_count, 2);
+        assert_eq!(archives[0].trust, 20.0);
+        assert_eq!(archives[0].performance, 10.0);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Source not found."#)]
+    fn remove_source_cleans_action_types() {
+        let mut contract = get_contract_with_two_sources();
+
+        contract.remove_source(accounts(1), None);
+        contract.get_source_action_types(accounts(1));
+    }
+
+    #[test]
+    fn continue_source_cleanup() {
+        let mut contract = get_contract_with_two_sources();
+
+        testing_env!(
+            get_context(accounts(0))
+                .prepaid_gas(CLEANUP_GAS_RESERVE)
+                .build()
+        );
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Purge));
+
+        let job = contract.get_source_cleanup(accounts(1)).unwrap();
+        assert_eq!(job.cursor, 0);
+        assert_eq!(job.total, 2);
+
+        testing_env!(get_context(accounts(0)).build());
+        let remaining = contract.continue_source_cleanup(accounts(1));
+
+        assert_eq!(remaining, 0);
+        assert!(contract.get_source_cleanup(accounts(1)).is_none());
+        assert_eq!(
+            contract.get_user_actions(
+                "coreto_website".to_string(),
+                "did:mock:accounts(3)".to_string(),
+                None,
+            ).len(),
+            0
+        );
+    }
+
+    #[test]
+    fn remove_source_purge_credits_storage() {
+        let mut contract = get_contract_with_two_sources();
+        let used_bytes = contract.storage_accounts.get(&accounts(1)).unwrap().used_bytes;
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Purge));
+
+        assert!(contract.storage_accounts.get(&accounts(1)).unwrap().used_bytes < used_bytes);
+    }
+
+    #[test]
+    fn redact_did_credits_storage() {
+        let mut contract = get_contract_with_two_sources();
+        let used_bytes = contract.storage_accounts.get(&accounts(1)).unwrap().used_bytes;
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.redact_did("did:mock:accounts(3)".to_string(), None);
+
+        assert!(contract.storage_accounts.get(&accounts(1)).unwrap().used_bytes < used_bytes);
+    }
+
+    #[test]
+    fn continue_source_cleanup_drops_done_dids() {
+        let mut contract = get_contract_with_two_sources();
+        for did in 0..30 {
+            testing_env!(get_context(accounts(1)).build());
+            save_mocked_action(&mut contract, &format!("did:mock:{}", did), "reaction", "w");
+        }
+
+        let context = || get_context(accounts(0)).prepaid_gas(CLEANUP_GAS_RESERVE + Gas(5_000_000_000_000)).build();
+        testing_env!(context());
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Purge));
+        let mut remaining = 33;
+        loop {
+            testing_env!(context());
+            let left = contract.continue_source_cleanup(accounts(1));
+            assert!(left < remaining);
+            remaining = left;
+            if left == 0 {
+                break;
+            }
+            assert_eq!(contract.get_source_cleanup(accounts(1)).unwrap().total, 32);
+        }
+
+        assert!(contract.source_dids.get(&accounts(1)).is_none());
+        assert!(contract.source_actions.get(&accounts(1)).is_none());
+    }
+
+    #[test]
+    fn continue_source_cleanup_within_did() {
+        let mut contract = get_contract_with_two_sources();
+        for identifier in 0..40 {
+            testing_env!(get_context(accounts(1)).build());
+            save_mocked_action(&mut contract, "did:mock:accounts(3)", "reaction", &format!("w{}", identifier));
+            testing_env!(get_context(accounts(2)).build());
+            save_mocked_action(&mut contract, "did:mock:accounts(3)", "article", &format!("a{}", identifier));
+        }
+
+        let context = || get_context(accounts(0)).prepaid_gas(CLEANUP_GAS_RESERVE + Gas(5_000_000_000_000)).build();
+        testing_env!(context());
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Archive));
+
+        let job = contract.get_source_cleanup(accounts(1)).unwrap();
+        assert_eq!(job.cursor, 1);
+        assert!(job.action_cursor > 0);
+
+        let mut calls = 1;
+        loop {
+            testing_env!(context());
+            calls += 1;
+            if contract.continue_source_cleanup(accounts(1)) == 0 {
+                break;
+            }
+        }
+        assert!(calls > 2);
+
+        let identifiers: Vec<String> = contract
+            .get_user_actions("coreto_app".to_string(), "did:mock:accounts(3)".to_string(), None)
+            .into_iter()
+            .map(|action| action.identifier)
+            .collect();
+        let expected: Vec<String> = std::iter::once("1".to_string())
+            .chain((0..40).map(|identifier| format!("a{}", identifier)))
+            .collect();
+        assert_eq!(identifiers, expected);
+
+        let archives = contract.get_user_archived_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+        );
+        assert_eq!(archives.len(), 1);
+        assert_eq!(archives[0].actions_count, 42);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Source cleanup in progress."#)]
+    fn add_source_during_cleanup() {
+        let mut contract = get_contract_with_two_sources();
+
+        testing_env!(
+            get_context(accounts(0))
+                .prepaid_gas(CLEANUP_GAS_RESERVE)
+                .build()
+        );
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Purge));
+        contract.add_source(accounts(1), "coreto_website".to_string());
+    }
+
+    #[test]
+    fn new_with_owner() {
+        let context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = CoretoTPLedger::new(accounts(5), None);
+        assert_eq!(contract.get_owner(), accounts(5));
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.add_source(accounts(1), "coreto_website".to_string());
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.propose_owner(accounts(5));
+
+        assert_eq!(contract.get_owner(), accounts(0));
+        assert_eq!(contract.get_pending_owner(), Some(accounts(5)));
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.accept_ownership();
+
+        assert_eq!(contract.get_owner(), accounts(5));
+        assert_eq!(contract.get_pending_owner(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Owner only."#)]
+    fn propose_owner_not_owner() {
+        testing_env!(get_context(accounts(1)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.propose_owner(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Not the pending owner."#)]
+    fn accept_ownership_not_pending_owner() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.propose_owner(accounts(5));
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.accept_ownership();
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
+    fn previous_owner_loses_access() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.propose_owner(accounts(5));
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.accept_ownership();
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.add_source(accounts(1), "coreto_website".to_string());
+    }
+
+    #[test]
+    fn grant_role() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.grant_role(accounts(5), CoretoTPRole::SourceManager);
+
+        assert!(contract.has_role(accounts(5), CoretoTPRole::SourceManager));
+        assert!(!contract.has_role(accounts(5), CoretoTPRole::WeightManager));
+        assert!(contract.has_role(accounts(0), CoretoTPRole::WeightManager));
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        contract.remove_source(accounts(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
+    fn revoke_role() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.grant_role(accounts(5), CoretoTPRole::SourceManager);
+        contract.revoke_role(accounts(5), CoretoTPRole::SourceManager);
+
+        assert!(!contract.has_role(accounts(5), CoretoTPRole::SourceManager));
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.add_source(accounts(1), "coreto_website".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Owner only."#)]
+    fn grant_role_not_owner() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.grant_role(accounts(5), CoretoTPRole::SourceManager);
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.grant_role(accounts(1), CoretoTPRole::SourceManager);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Owner role is transferred with propose_owner."#)]
+    fn grant_owner_role() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.grant_role(accounts(5), CoretoTPRole::Owner);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Ingestion is paused."#)]
+    fn save_action_paused() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.pause(vec![CoretoTPPauseCategory::Ingestion]);
+
+        testing_env!(get_context(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "124");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Ingestion is paused."#)]
+    fn save_actions_batch_paused_all() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        contract.pause(vec![CoretoTPPauseCategory::All]);
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.save_actions_batch(vec![], None);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DidRegistry is paused."#)]
+    fn set_consent_paused_all() {
+        let mut contract = get_contract_with_near_did_action();
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.pause(vec![CoretoTPPauseCategory::All]);
+
+        testing_env!(get_context(accounts(2)).build());
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), Vec::new(), true);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"SourceManagement is paused."#)]
+    fn set_source_public_key_paused() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.pause(vec![CoretoTPPauseCategory::SourceManagement]);
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.set_source_public_key(None);
+    }
+
+    #[test]
+    fn pause_keeps_views_and_unpause_resumes_writes() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.pause(vec![CoretoTPPauseCategory::Ingestion]);
+
+        assert!(contract.is_paused(CoretoTPPauseCategory::Ingestion));
+        assert!(!contract.is_paused(CoretoTPPauseCategory::SourceManagement));
+        assert_eq!(
+            contract.get_user_actions(
+                "coreto_website".to_string(),
+                "did:mock:accounts(2)".to_string(),
+                None,
+            ).len(),
+            1
+        );
+
+        contract.add_source(accounts(3), "coreto_app".to_string());
+        deposit_storage(&mut contract, accounts(3));
+        contract.unpause(vec![CoretoTPPauseCategory::Ingestion]);
+        assert!(contract.get_paused().is_empty());
+
+        testing_env!(get_context(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "124");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Owner only."#)]
+    fn pause_not_owner() {
+        testing_env!(get_context(accounts(1)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.pause(vec![CoretoTPPauseCategory::All]);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid signer wallet."#)]
+    fn save_action_signer_without_opt_in() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(
+            context
+                .signer_account_id(accounts(1))
+                .predecessor_account_id(accounts(5))
+                .build()
+        );
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "1");
+    }
+
+    #[test]
+    fn save_action_signer_with_opt_in() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.set_signer_submission(true);
+        assert!(contract.get_source(accounts(1)).unwrap().allow_signer_submission);
+
+        testing_env!(
+            context
+                .signer_account_id(accounts(1))
+                .predecessor_account_id(accounts(5))
+                .build()
+        );
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "1");
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(2)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].source, accounts(1));
+    }
+
+    #[test]
+    fn save_action_cross_contract_source() {
+        let mut context = get_context(accounts(0));
+        testing_env!(context.build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(
+            context
+                .signer_account_id(accounts(5))
+                .predecessor_account_id(accounts(1))
+                .build()
+        );
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "1");
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(2)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].source, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Source not found."#)]
+    fn set_signer_submission_not_source() {
+        testing_env!(get_context(accounts(1)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.set_signer_submission(true);
+    }
+
+    fn get_mocked_keypair() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn get_near_public_key(keypair: &ed25519_dalek::Keypair) -> PublicKey {
+        PublicKey::try_from([vec![0], keypair.public.to_bytes().to_vec()].concat()).unwrap()
+    }
+
+    fn get_mocked_batch() -> Vec<CoretoTPActionRequestData> {
+        vec![
+            CoretoTPActionRequestData {
+                action_type: "reaction".to_string(),
+                performance: 10.0,
+                trust: 10.0,
+                action_date: "1640995200".to_string(),
+                account_did: "did:mock:accounts(3)".to_string(),
+                identifier: "123".to_string(),
+            },
+        ]
+    }
+
+    fn get_contract_with_source_key(keypair: &ed25519_dalek::Keypair) -> CoretoTPLedger {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.set_source_public_key(Some(get_near_public_key(keypair)));
+
+        contract
+    }
+
+    fn sign_batch(keypair: &ed25519_dalek::Keypair, nonce: u64, batch: &[CoretoTPActionRequestData]) -> Base64VecU8 {
+        let message = relayer::signed_batch_hash(&accounts(0), &accounts(1), nonce, batch);
+
+        keypair.sign(&message).to_bytes().to_vec().into()
+    }
+
+    #[test]
+    fn save_signed_actions_batch() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+        let batch = get_mocked_batch();
+        let signature = sign_batch(&keypair, 1, &batch);
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.save_signed_actions_batch(accounts(1), batch, 1, signature);
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].source, accounts(1));
+        assert_eq!(contract.get_source(accounts(1)).unwrap().nonce, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid nonce."#)]
+    fn save_signed_actions_batch_replayed() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+        let batch = get_mocked_batch();
+        let signature = sign_batch(&keypair, 1, &batch);
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.save_signed_actions_batch(accounts(1), get_mocked_batch(), 1, signature.clone());
+        contract.save_signed_actions_batch(accounts(1), batch, 1, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid signature."#)]
+    fn save_signed_actions_batch_tampered() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+        let signature = sign_batch(&keypair, 1, &get_mocked_batch());
+
+        let mut batch = get_mocked_batch();
+        batch[0].trust = 100.0;
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.save_signed_actions_batch(accounts(1), batch, 1, signature);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Source has no public key."#)]
+    fn save_signed_actions_batch_no_key() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.save_signed_actions_batch(accounts(1), get_mocked_batch(), 1, vec![0; 64].into());
+    }
+
+    fn get_contract_with_submitter() -> CoretoTPLedger {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.add_source_submitter(accounts(1), accounts(4));
+
+        contract
+    }
+
+    #[test]
+    fn save_action_as_submitter() {
+        let mut contract = get_contract_with_submitter();
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.add_source_submitter(accounts(1), accounts(5));
+        assert_eq!(contract.get_source_submitters(accounts(1)), vec![accounts(4), accounts(5)]);
+
+        testing_env!(get_context(accounts(4)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "1");
+
+        testing_env!(get_context(accounts(5)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "2");
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(2)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].source, accounts(1));
+        assert_eq!(actions[1].source_label, "coreto_website");
+        assert_eq!(contract.get_source_action_types(accounts(1)), vec!["reaction".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid signer wallet."#)]
+    fn save_action_revoked_submitter() {
+        let mut contract = get_contract_with_submitter();
+
+        contract.remove_source_submitter(accounts(1), accounts(4));
+        assert!(contract.get_source_submitters(accounts(1)).is_empty());
+
+        testing_env!(get_context(accounts(4)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "1");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid signer wallet."#)]
+    fn save_action_submitter_of_removed_source() {
+        let mut contract = get_contract_with_submitter();
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.remove_source(accounts(1), None);
+
+        testing_env!(get_context(accounts(4)).build());
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "1");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
+    fn add_source_submitter_not_authorised() {
+        let mut contract = get_contract_with_submitter();
+
+        testing_env!(get_context(accounts(4)).build());
+        contract.add_source_submitter(accounts(1), accounts(5));
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Submitter already exists."#)]
+    fn add_source_submitter_twice() {
+        let mut contract = get_contract_with_submitter();
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.add_source(accounts(2), "coreto_app".to_string());
+        deposit_storage(&mut contract, accounts(2));
+        contract.add_source_submitter(accounts(2), accounts(4));
+    }
+    #[test]
+    fn register_action_type() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+
+        testing_env!(get_context(accounts(1)).build());
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        let action_type = contract.get_source_action_type(accounts(1), "reaction".to_string()).unwrap();
+        assert_eq!(action_type.description, "reaction action");
+        assert_eq!(action_type.max_trust, 100.0);
+        assert_eq!(action_type.weight, 1.0);
+        assert_eq!(contract.get_source_action_types(accounts(1)), vec!["reaction".to_string()]);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action type not registered."#)]
+    fn save_action_unregistered_type() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reacton", "124");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Trust out of bounds."#)]
+    fn save_action_trust_out_of_bounds() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.save_action(
+            "did:mock:accounts(2)".to_string(),
+            1e30,
+            10.0,
+            "reaction".to_string(),
+            "1640995200".to_string(),
+            "124".to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Performance out of bounds."#)]
+    fn save_actions_batch_performance_out_of_bounds() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        let mut batch = get_mocked_batch();
+        batch[0].performance = f32::NAN;
+        contract.save_actions_batch(batch, None);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action type already exists."#)]
+    fn register_action_type_twice() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
+    fn register_action_type_not_authorised() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(5)).build());
+        register_mocked_action_type(&mut contract, accounts(1), "article");
+    }
+
+    #[test]
+    fn get_user_trust_weighted() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        register_mocked_action_type(&mut contract, accounts(1), "article");
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "article", "124");
+
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(20.0)
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_action_type_weight(accounts(1), "article".to_string(), 0.5);
+
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(15.0)
+        );
+        assert_eq!(
+            contract.get_user_performance("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(12.5)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing WeightManager role."#)]
+    fn set_action_type_weight_not_authorised() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.set_action_type_weight(accounts(1), "reaction".to_string(), 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action type is deprecated."#)]
+    fn save_action_deprecated_type() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.set_action_type_status(accounts(1), "reaction".to_string(), CoretoTPActionTypeStatus::Deprecated);
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "124");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action type is retired."#)]
+    fn save_actions_batch_retired_type() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_action_type_status(accounts(1), "reaction".to_string(), CoretoTPActionTypeStatus::Retired);
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.save_actions_batch(get_mocked_batch(), None);
+    }
+
+    #[test]
+    fn action_type_status_and_scoring() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        register_mocked_action_type(&mut contract, accounts(1), "article");
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "article", "124");
+
+        contract.set_action_type_status(accounts(1), "reaction".to_string(), CoretoTPActionTypeStatus::Deprecated);
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(20.0)
+        );
+
+        contract.set_action_type_status(accounts(1), "reaction".to_string(), CoretoTPActionTypeStatus::Retired);
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(10.0)
+        );
+
+        let details = contract.get_source_action_type_details(accounts(1));
+        assert_eq!(details.len(), 2);
+        assert_eq!(details[0].0, "reaction");
+        assert_eq!(details[0].1.status, CoretoTPActionTypeStatus::Retired);
+        assert_eq!(details[1].1.status, CoretoTPActionTypeStatus::Active);
+    }
+
+    #[test]
+    fn remove_source_keeps_action_type_weights() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        register_mocked_action_type(&mut contract, accounts(1), "article");
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "article", "124");
+        contract.set_action_type_status(accounts(1), "reaction".to_string(), CoretoTPActionTypeStatus::Retired);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_action_type_weight(accounts(1), "article".to_string(), 0.5);
+        contract.remove_source(accounts(1), None);
+
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(5.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing SourceManager role."#)]
+    fn set_action_type_status_not_authorised() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.set_action_type_status(accounts(1), "reaction".to_string(), CoretoTPActionTypeStatus::Deprecated);
+    }
+    #[test]
+    fn retract_action() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "124");
+
+        contract.retract_action("123".to_string(), "Created by a bug".to_string());
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(2)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].identifier, "124");
+
+        let all_actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(2)".to_string(),
+            Some(true),
+        );
+        assert_eq!(all_actions.len(), 2);
+        assert_eq!(all_actions[0].retraction.as_ref().unwrap().reason, "Created by a bug");
+
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(10.0)
+        );
+        assert_eq!(
+            contract.get_user_trust_actions(
+                "coreto_website".to_string(),
+                "did:mock:accounts(2)".to_string(),
+                None,
+            ).len(),
+            1
+        );
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"action_retracted","data":{"source":"bob","identifier":"123","reason":"Created by a bug"}}"#
+        );
+    }
+
+    #[test]
+    fn retract_action_after_purge_of_other_source() {
+        let mut contract = get_contract_with_two_sources();
+
+        contract.remove_source(accounts(2), Some(CoretoTPSourceCleanup::Purge));
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.retract_action("2".to_string(), "Fraud".to_string());
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:mock:accounts(3)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].identifier, "1");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action not found."#)]
+    fn retract_action_other_source() {
+        let mut contract = get_contract_with_two_sources();
+
+        testing_env!(get_context(accounts(2)).build());
+        contract.retract_action("2".to_string(), "Fraud".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action already retracted."#)]
+    fn retract_action_twice() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.retract_action("123".to_string(), "Created by a bug".to_string());
+        contract.retract_action("123".to_string(), "Created by a bug".to_string());
+    }
+    #[test]
+    fn amend_action() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.amend_action("123".to_string(), 4.0, 2.0, "Moderated".to_string());
+        contract.amend_action("123".to_string(), 6.0, 3.0, "Moderated again".to_string());
+
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(6.0)
+        );
+        assert_eq!(
+            contract.get_user_performance("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(3.0)
+        );
+
+        let history = contract.get_action_history(accounts(1), "123".to_string());
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].trust, 6.0);
+        assert_eq!(history[0].revisions.len(), 2);
+        assert_eq!(history[0].revisions[0].trust, 10.0);
+        assert_eq!(history[0].revisions[0].reason, "Moderated");
+        assert_eq!(history[0].revisions[1].trust, 4.0);
+        assert_eq!(history[0].revisions[1].performance, 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Trust out of bounds."#)]
+    fn amend_action_out_of_bounds() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.amend_action("123".to_string(), 1e30, 2.0, "Moderated".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action is retracted."#)]
+    fn amend_action_retracted() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.retract_action("123".to_string(), "Created by a bug".to_string());
+        contract.amend_action("123".to_string(), 4.0, 2.0, "Moderated".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action not found."#)]
+    fn get_action_history_not_found() {
+        let contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.get_action_history(accounts(1), "124".to_string());
+    }
+    fn get_contract_with_near_did_action() -> CoretoTPLedger {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        save_mocked_action(&mut contract, "did:near:charlie", "reaction", "124");
+        save_mocked_action(&mut contract, "did:near:charlie", "reaction", "125");
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.grant_role(accounts(4), CoretoTPRole::Arbiter);
+
+        testing_env!(get_context(accounts(2)).build());
+        contract
+    }
+
+    #[test]
+    fn open_dispute() {
+        let mut contract = get_contract_with_near_did_action();
+
+        let dispute_id = contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            Some(Base58CryptoHash::from([7; 32])),
+        );
+
+        let dispute = contract.get_dispute(dispute_id).unwrap();
+        assert_eq!(dispute.status, CoretoTPDisputeStatus::Pending);
+        assert_eq!(dispute.opened_by, Some(accounts(2)));
+        assert_eq!(dispute.evidence_hash, Some(Base58CryptoHash::from([7; 32])));
+        assert_eq!(contract.get_disputes(None, None).len(), 1);
+        assert_eq!(contract.get_user_disputes("did:near:charlie".to_string(), None, None).len(), 1);
+        assert_eq!(contract.get_user_disputes("did:near:charlie".to_string(), Some(1), None).len(), 0);
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:near:charlie".to_string()),
+            CoretoTPScore::Value(20.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Reason too long."#)]
+    fn open_dispute_reason_too_long() {
+        let mut contract = get_contract_with_near_did_action();
+
+        contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "a".repeat(257),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Not the DID controller."#)]
+    fn open_dispute_not_controller() {
+        let mut contract = get_contract_with_near_did_action();
+
+        testing_env!(get_context(accounts(3)).build());
+        contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Action already disputed."#)]
+    fn open_dispute_twice() {
+        let mut contract = get_contract_with_near_did_action();
+
+        for _ in 0..2 {
+            contract.open_dispute(
+                "did:near:charlie".to_string(),
+                accounts(1),
+                "124".to_string(),
+                "Unfair".to_string(),
+                None,
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_dispute_upheld() {
+        let mut contract = get_contract_with_near_did_action();
+        let dispute_id = contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+
+        testing_env!(get_context(accounts(4)).build());
+        contract.set_dispute_frozen(dispute_id, true);
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:near:charlie".to_string()),
+            CoretoTPScore::Value(10.0)
+        );
+
+        contract.resolve_dispute(dispute_id, true, "Confirmed".to_string());
+        assert_eq!(
+            get_logs()[1..],
+            [
+                r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"action_retracted","data":{"source":"bob","identifier":"124","reason":"Dispute 0 upheld: Confirmed"}}"#,
+                r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"dispute_resolved","data":{"dispute_id":0,"upheld":true}}"#,
+            ]
+        );
+
+        let dispute = contract.get_dispute(dispute_id).unwrap();
+        assert_eq!(dispute.status, CoretoTPDisputeStatus::Upheld);
+        assert_eq!(dispute.resolved_by, Some(accounts(4)));
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:near:charlie".to_string()),
+            CoretoTPScore::Value(10.0)
+        );
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "did:near:charlie".to_string(),
+            Some(true),
+        );
+        assert_eq!(actions[0].retraction.as_ref().unwrap().reason, "Dispute 0 upheld: Confirmed");
+        assert_eq!(actions[0].dispute_id, None);
+    }
+
+    #[test]
+    fn resolve_dispute_rejected() {
+        let mut contract = get_contract_with_near_did_action();
+        let dispute_id = contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+
+        testing_env!(get_context(accounts(4)).build());
+        contract.set_dispute_frozen(dispute_id, true);
+        contract.resolve_dispute(dispute_id, false, "Fair".to_string());
+
+        assert_eq!(contract.get_dispute(dispute_id).unwrap().status, CoretoTPDisputeStatus::Rejected);
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:near:charlie".to_string()),
+            CoretoTPScore::Value(20.0)
+        );
+    }
+
+    fn open_mocked_dispute(contract: &mut CoretoTPLedger) -> u64 {
+        contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn remove_source_voids_disputes() {
+        let mut contract = get_contract_with_near_did_action();
+        let dispute_id = open_mocked_dispute(&mut contract);
+
+        testing_env!(get_context(accounts(4)).build());
+        contract.set_dispute_frozen(dispute_id, true);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.remove_source(accounts(1), Some(CoretoTPSourceCleanup::Purge));
+
+        let dispute = contract.get_dispute(dispute_id).unwrap();
+        assert_eq!(dispute.status, CoretoTPDisputeStatus::Void);
+        assert!(!dispute.frozen);
+        assert!(get_logs().contains(
+            &r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"dispute_voided","data":{"dispute_id":0}}"#.to_string()
+        ));
+    }
+
+    #[test]
+    fn redact_did_voids_disputes() {
+        let mut contract = get_contract_with_near_did_action();
+        let dispute_id = open_mocked_dispute(&mut contract);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.grant_role(accounts(0), CoretoTPRole::Moderator);
+        contract.redact_did("did:near:charlie".to_string(), None);
+
+        assert_eq!(contract.get_dispute(dispute_id).unwrap().status, CoretoTPDisputeStatus::Void);
+    }
+
+    #[test]
+    fn resolve_dispute_action_removed() {
+        let mut contract = get_contract_with_near_did_action();
+        let dispute_id = open_mocked_dispute(&mut contract);
+        contract.action_index.remove(&(accounts(1), "124".to_string()));
+
+        testing_env!(get_context(accounts(4)).build());
+        contract.resolve_dispute(dispute_id, true, "Confirmed".to_string());
+
+        let dispute = contract.get_dispute(dispute_id).unwrap();
+        assert_eq!(dispute.status, CoretoTPDisputeStatus::Void);
+        assert_eq!(dispute.resolved_by, Some(accounts(4)));
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"dispute_voided","data":{"dispute_id":0}}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing Moderator or Arbiter role."#)]
+    fn resolve_dispute_not_authorised() {
+        let mut contract = get_contract_with_near_did_action();
+        let dispute_id = contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+
+        contract.resolve_dispute(dispute_id, false, "Fair".to_string());
+    }
+    fn sign_did_claim(keypair: &ed25519_dalek::Keypair, account_did: &str, account_id: AccountId) -> Base64VecU8 {
+        let message = dids::did_claim_hash(&accounts(0), &account_did.to_string(), &account_id);
+
+        keypair.sign(&message).to_bytes().to_vec().into()
+    }
+
+    fn get_mocked_did_key(keypair: &ed25519_dalek::Keypair) -> String {
+        let key = [vec![0xed, 0x01], keypair.public.to_bytes().to_vec()].concat();
+
+        format!("did:key:z{}", near_sdk::bs58::encode(key).into_string())
+    }
+
+    #[test]
+    fn claim_did_with_source_signature() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+
+        testing_env!(get_context(accounts(5)).attached_deposit(CLAIM_DEPOSIT).build());
+        contract.claim_did(
+            "did:mock:accounts(2)".to_string(),
+            CoretoTPDidProof::SourceSignature {
+                source: accounts(1),
+                signature: sign_did_claim(&keypair, "did:mock:accounts(2)", accounts(5)),
+            },
+        );
+
+        assert_eq!(contract.get_did_controller("did:mock:accounts(2)".to_string()), Some(accounts(5)));
+        assert_eq!(contract.get_controlled_dids(accounts(5)), vec!["did:mock:accounts(2)".to_string()]);
+
+        contract.release_did("did:mock:accounts(2)".to_string());
+        assert_eq!(contract.get_did_controller("did:mock:accounts(2)".to_string()), None);
+        assert!(contract.get_controlled_dids(accounts(5)).is_empty());
+    }
+
+    #[test]
+    fn claim_did_with_did_key() {
+        let keypair = get_mocked_keypair();
+        let account_did = get_mocked_did_key(&keypair);
+
+        testing_env!(get_context(accounts(5)).attached_deposit(CLAIM_DEPOSIT).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.claim_did(
+            account_did.clone(),
+            CoretoTPDidProof::DidKeySignature {
+                signature: sign_did_claim(&keypair, &account_did, accounts(5)),
+            },
+        );
+
+        assert_eq!(contract.get_did_controller(account_did), Some(accounts(5)));
+        assert_eq!(contract.get_did_controller("did:near:bob".to_string()), Some(accounts(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid DID proof."#)]
+    fn claim_did_signed_for_other_account() {
+        let keypair = get_mocked_keypair();
+        let account_did = get_mocked_did_key(&keypair);
+
+        testing_env!(get_context(accounts(5)).attached_deposit(CLAIM_DEPOSIT).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.claim_did(
+            account_did.clone(),
+            CoretoTPDidProof::DidKeySignature {
+                signature: sign_did_claim(&keypair, &account_did, accounts(4)),
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid DID proof."#)]
+    fn claim_did_key_signature_for_other_method() {
+        let keypair = get_mocked_keypair();
+
+        testing_env!(get_context(accounts(5)).attached_deposit(CLAIM_DEPOSIT).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.claim_did(
+            "did:web:example.com".to_string(),
+            CoretoTPDidProof::DidKeySignature {
+                signature: sign_did_claim(&keypair, "did:web:example.com", accounts(5)),
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DID already claimed."#)]
+    fn claim_did_already_claimed() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+
+        for account_id in [accounts(5), accounts(4)] {
+            testing_env!(get_context(account_id.clone()).attached_deposit(CLAIM_DEPOSIT).build());
+            contract.claim_did(
+                "did:mock:accounts(2)".to_string(),
+                CoretoTPDidProof::SourceSignature {
+                    source: accounts(1),
+                    signature: sign_did_claim(&keypair, "did:mock:accounts(2)", account_id),
+                },
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Insufficient deposit for storage."#)]
+    fn claim_did_without_deposit() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+
+        testing_env!(get_context(accounts(5)).build());
+        contract.claim_did(
+            "did:mock:accounts(2)".to_string(),
+            CoretoTPDidProof::SourceSignature {
+                source: accounts(1),
+                signature: sign_did_claim(&keypair, "did:mock:accounts(2)", accounts(5)),
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Too many DIDs claimed."#)]
+    fn claim_did_too_many() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+
+        for did in 0..21 {
+            let account_did = format!("did:mock:{}", did);
+            testing_env!(get_context(accounts(5)).attached_deposit(CLAIM_DEPOSIT).build());
+            contract.claim_did(
+                account_did.clone(),
+                CoretoTPDidProof::SourceSignature {
+                    source: accounts(1),
+                    signature: sign_did_claim(&keypair, &account_did, accounts(5)),
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn open_dispute_as_claimed_controller() {
+        let keypair = get_mocked_keypair();
+        let mut contract = get_contract_with_source_key(&keypair);
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "123");
+
+        testing_env!(get_context(accounts(5)).attached_deposit(CLAIM_DEPOSIT).build());
+        contract.claim_did(
+            "did:mock:accounts(2)".to_string(),
+            CoretoTPDidProof::SourceSignature {
+                source: accounts(1),
+                signature: sign_did_claim(&keypair, "did:mock:accounts(2)", accounts(5)),
+            },
+        );
+        contract.open_dispute(
+            "did:mock:accounts(2)".to_string(),
+            accounts(1),
+            "123".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+    }
+    #[test]
+    fn normalize_did() {
+        assert_eq!(
+            dids::normalize_did("DID:Mock:accounts(2)"),
+            Some("did:mock:accounts(2)".to_string())
+        );
+        assert_eq!(
+            dids::normalize_did("did:web:example.com:user:Alice"),
+            Some("did:web:example.com:user:Alice".to_string())
+        );
+        assert_eq!(dids::normalize_did("did:mock"), None);
+        assert_eq!(dids::normalize_did("did::accounts(2)"), None);
+        assert_eq!(dids::normalize_did("did:mo-ck:accounts(2)"), None);
+        assert_eq!(dids::normalize_did("did:mock:accounts 2"), None);
+        assert_eq!(dids::normalize_did("did:mock:accounts#2"), None);
+        assert_eq!(dids::normalize_did("did:mock:accounts:"), None);
+        assert_eq!(dids::normalize_did("uri:mock:accounts(2)"), None);
+        assert_eq!(dids::normalize_did(&format!("did:mock:{}", "a".repeat(dids::MAX_DID_LENGTH))), None);
+    }
+
+    #[test]
+    fn save_action_normalizes_did() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        save_mocked_action(&mut contract, "DID:Mock:accounts(2)", "reaction", "124");
+
+        let actions = contract.get_user_actions(
+            "coreto_website".to_string(),
+            "Did:MOCK:accounts(2)".to_string(),
+            None,
+        );
+        assert_eq!(actions.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid DID."#)]
+    fn save_action_invalid_did() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        save_mocked_action(&mut contract, "accounts(2)", "reaction", "124");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid DID."#)]
+    fn get_user_trust_invalid_did() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let contract = CoretoTPLedger::default();
+        contract.get_user_trust("coreto_website".to_string(), "did:mock".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DID method not allowed."#)]
+    fn save_actions_batch_did_method_not_allowed() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_did_methods(Some(vec!["NEAR".to_string()]));
+        assert_eq!(contract.get_did_methods(), Some(vec!["near".to_string()]));
+
+        testing_env!(get_context(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:near:charlie", "reaction", "124");
+        contract.save_actions_batch(get_mocked_batch(), None);
+    }
+    fn get_contract_with_merged_dids() -> CoretoTPLedger {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        save_mocked_action(&mut contract, "did:mock:new", "reaction", "124");
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.merge_did("did:mock:new".to_string(), "did:mock:accounts(2)".to_string());
+
+        contract
+    }
+
+    #[test]
+    fn merge_did() {
+        let contract = get_contract_with_merged_dids();
+
+        assert_eq!(contract.get_canonical_did("did:mock:accounts(2)".to_string()), "did:mock:new");
+        assert_eq!(
+            contract.get_did_aliases("did:mock:new".to_string()),
+            vec!["did:mock:accounts(2)".to_string()]
+        );
+        assert_eq!(
+            get_logs(),
+            vec![r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"did_merged","data":{"canonical_did":"did:mock:new","alias":"did:mock:accounts(2)"}}"#]
+        );
+
+        for account_did in ["did:mock:new", "did:mock:accounts(2)"] {
+            let actions = contract.get_user_actions("coreto_website".to_string(), account_did.to_string(), None);
+            assert_eq!(actions.len(), 2);
+            assert_eq!(actions[0].identifier, "124");
+            assert_eq!(
+                contract.get_user_trust("coreto_website".to_string(), account_did.to_string()),
+                CoretoTPScore::Value(20.0)
+            );
+        }
+    }
+
+    #[test]
+    fn unmerge_did() {
+        let mut contract = get_contract_with_merged_dids();
+
+        contract.unmerge_did("did:mock:accounts(2)".to_string());
+
+        assert_eq!(contract.get_canonical_did("did:mock:accounts(2)".to_string()), "did:mock:accounts(2)");
+        assert!(contract.get_did_aliases("did:mock:new".to_string()).is_empty());
+        assert_eq!(contract.get_user_trust("coreto_website".to_string(), "did:mock:new".to_string()), CoretoTPScore::Value(10.0));
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(10.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing Moderator role."#)]
+    fn merge_did_not_moderator() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.merge_did("did:mock:new".to_string(), "did:mock:accounts(2)".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DID already merged."#)]
+    fn merge_did_already_merged() {
+        let mut contract = get_contract_with_merged_dids();
+
+        contract.merge_did("did:mock:other".to_string(), "did:mock:accounts(2)".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Canonical DID is an alias."#)]
+    fn merge_did_into_alias() {
+        let mut contract = get_contract_with_merged_dids();
+
+        contract.merge_did("did:mock:accounts(2)".to_string(), "did:mock:other".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DID not merged."#)]
+    fn unmerge_did_not_merged() {
+        let mut contract = get_contract_with_merged_dids();
+
+        contract.unmerge_did("did:mock:new".to_string());
+    }
+
+    #[test]
+    fn redact_did_summary() {
+        let mut contract = get_contract_with_two_sources();
+
+        let redaction_id = contract.redact_did("did:mock:accounts(3)".to_string(), None);
+
+        let redaction = contract.get_redaction(redaction_id).unwrap();
+        assert_eq!(redaction.mode, CoretoTPRedactionMode::Summary);
+        assert_eq!(redaction.did_hash, env::sha256_array(b"did:mock:accounts(3)").into());
+        assert_eq!(redaction.actions_count, 3);
+        assert!(redaction.completed_date.is_some());
+        assert_eq!(redaction.summaries.len(), 2);
+        assert_eq!(redaction.summaries[0].source, accounts(2));
+        assert_eq!(redaction.summaries[1].actions_count, 2);
+        assert_eq!(redaction.summaries[1].trust, 20.0);
+        assert_eq!(contract.get_redactions(None, None).len(), 1);
+
+        assert!(!contract.tp_infos.contains_key(&"did:mock:accounts(3)".to_string()));
+        assert!(!contract.source_dids.get(&accounts(1)).unwrap().contains(&"did:mock:accounts(3)".to_string()));
+        assert_eq!(contract.get_action_history(accounts(1), "3".to_string()).len(), 1);
+        assert!(contract.action_index.get(&(accounts(1), "1".to_string())).is_none());
+        assert_eq!(contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(3)".to_string()), CoretoTPScore::Value(0.0));
+    }
+
+    #[test]
+    fn redact_did_erases_consent() {
+        let mut contract = get_contract_with_near_did_action();
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), vec![accounts(1)], true);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.redact_did("did:near:charlie".to_string(), None);
+
+        assert!(contract.get_consent_history("did:near:charlie".to_string(), None, None).is_empty());
+        assert!(contract.get_consent("did:near:charlie".to_string()).is_none());
+    }
+
+    #[test]
+    fn redact_did_tombstone() {
+        let mut contract = get_contract_with_near_did_action();
+        contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        let redaction_id = contract.redact_did("did:near:charlie".to_string(), Some(CoretoTPRedactionMode::Tombstone));
+
+        let redaction = contract.get_redaction(redaction_id).unwrap();
+        assert_eq!(redaction.actions_count, 2);
+        assert!(redaction.summaries.is_empty());
+        assert!(contract.get_user_disputes("did:near:charlie".to_string(), None, None).is_empty());
+
+        let dispute = contract.get_dispute(0).unwrap();
+        assert_eq!(dispute.account_did, "");
+        assert_eq!(dispute.opened_by, None);
+        assert_eq!(dispute.reason, "");
+    }
+
+    #[test]
+    fn redact_did_without_actions() {
+        let mut contract = get_contract_with_near_did_action();
+
+        testing_env!(get_context(accounts(3)).build());
+        set_mocked_consent(&mut contract, "did:near:danny".to_string(), vec![accounts(1)], true);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.set_did_methods(Some(vec!["mock".to_string()]));
+        let redaction_id = contract.redact_did("did:near:danny".to_string(), None);
+
+        let redaction = contract.get_redaction(redaction_id).unwrap();
+        assert_eq!(redaction.actions_count, 0);
+        assert!(redaction.completed_date.is_some());
+        assert!(contract.consents.get(&"did:near:danny".to_string()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = r#"AccountDID not found."#)]
+    fn redact_did_unknown() {
+        let mut contract = get_contract_with_near_did_action();
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.redact_did("did:near:danny".to_string(), None);
+    }
+
+    #[test]
+    fn redact_did_continue() {
+        let mut contract = get_contract_with_two_sources();
+
+        let mut context = get_context(accounts(0));
+        testing_env!(context.prepaid_gas(CLEANUP_GAS_RESERVE).build());
+        contract.redact_did("did:mock:accounts(3)".to_string(), None);
+        assert!(contract.get_redaction(0).unwrap().completed_date.is_none());
+
+        testing_env!(context.prepaid_gas(Gas(300_000_000_000_000)).build());
+        assert_eq!(contract.continue_redaction("did:mock:accounts(3)".to_string()), 0);
+        assert_eq!(contract.get_redaction(0).unwrap().actions_count, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Missing Moderator role."#)]
+    fn redact_did_not_moderator() {
+        let mut contract = get_contract_with_two_sources();
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.redact_did("did:mock:accounts(3)".to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"AccountDID not found."#)]
+    fn redact_did_removes_actions() {
+        let mut contract = get_contract_with_two_sources();
+
+        contract.redact_did("did:mock:accounts(3)".to_string(), None);
+        contract.get_user_actions("coreto_website".to_string(), "did:mock:accounts(3)".to_string(), None);
+    }
+
+    #[test]
+    fn pseudonymous_did_storage() {
+        testing_env!(get_context(accounts(0)).build());
+
+        let mut contract = CoretoTPLedger::new(accounts(0), Some(Base64VecU8(b"salt".to_vec())));
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(get_context(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:near:charlie", "reaction", "124");
+
+        let did_key = contract.get_did_key("DID:near:charlie".to_string());
+        assert_ne!(did_key, "did:near:charlie");
+        assert!(contract.tp_infos.contains_key(&did_key));
+        assert!(!contract.tp_infos.contains_key(&"did:near:charlie".to_string()));
+        assert_eq!(contract.get_user_trust("coreto_website".to_string(), "did:near:charlie".to_string()), CoretoTPScore::Value(10.0));
+
+        testing_env!(get_context(accounts(2)).build());
+        let dispute_id = contract.open_dispute(
+            "did:near:charlie".to_string(),
+            accounts(1),
+            "124".to_string(),
+            "Unfair".to_string(),
+            None,
+        );
+        assert_eq!(contract.get_dispute(dispute_id).unwrap().account_did, did_key);
+        assert_eq!(contract.get_user_disputes("did:near:charlie".to_string(), None, None).len(), 1);
+    }
+
+    #[test]
+    fn pseudonymous_did_claim() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = CoretoTPLedger::new(accounts(0), Some(Base64VecU8(b"salt".to_vec())));
+        let keypair = get_mocked_keypair();
+        let account_did = get_mocked_did_key(&keypair);
+
+        testing_env!(get_context(accounts(3)).attached_deposit(CLAIM_DEPOSIT).build());
+        contract.claim_did(
+            account_did.clone(),
+            CoretoTPDidProof::DidKeySignature {
+                signature: sign_did_claim(&keypair, &account_did, accounts(3)),
+            },
+        );
+
+        assert_eq!(contract.get_did_controller(account_did.clone()), Some(accounts(3)));
+        assert_eq!(
+            contract.get_controlled_dids(accounts(3)),
+            vec![contract.get_did_key(account_did)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid DID salt."#)]
+    fn new_with_empty_did_salt() {
+        testing_env!(get_context(accounts(0)).build());
+
+        CoretoTPLedger::new(accounts(0), Some(Base64VecU8(Vec::new())));
+    }
+
+    #[test]
+    fn set_consent() {
+        let mut contract = get_contract_with_near_did_action();
+
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), vec![accounts(3)], true);
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), Vec::new(), true);
+
+        let consent = contract.get_consent("did:near:charlie".to_string()).unwrap();
+        assert!(consent.excluded_sources.is_empty());
+        assert_eq!(consent.changed_by, accounts(2));
+        assert_eq!(contract.get_consent_history("did:near:charlie".to_string(), None, None).len(), 2);
+
+        let trust = contract.get_user_trust("coreto_website".to_string(), "did:near:charlie".to_string());
+        assert_eq!(trust, CoretoTPScore::Withheld);
+        assert_eq!(serde_json::to_string(&trust).unwrap(), r#""withheld""#);
+        assert_eq!(
+            serde_json::to_string(&CoretoTPScore::Value(1.5)).unwrap(),
+            "1.5"
+        );
+    }
+
+    #[test]
+    fn set_consent_history_kept() {
+        let mut contract = get_contract_with_near_did_action();
+
+        for _ in 0..15 {
+            set_mocked_consent(&mut contract, "did:near:charlie".to_string(), Vec::new(), false);
+        }
+
+        assert_eq!(contract.get_consent_history("did:near:charlie".to_string(), None, None).len(), 15);
+        assert_eq!(contract.get_consent_history("did:near:charlie".to_string(), Some(10), Some(10)).len(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Insufficient deposit for storage."#)]
+    fn set_consent_without_deposit() {
+        let mut contract = get_contract_with_near_did_action();
+
+        contract.set_consent("did:near:charlie".to_string(), Vec::new(), false);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DID opted out of the source."#)]
+    fn save_action_excluded_source_by_alias() {
+        let mut contract = get_contract_with_near_did_action();
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), vec![accounts(1)], false);
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.merge_did("did:mock:new".to_string(), "did:near:charlie".to_string());
+
+        testing_env!(get_context(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:mock:new", "reaction", "126");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Scores withheld."#)]
+    fn get_user_actions_withheld() {
+        let mut contract = get_contract_with_near_did_action();
+
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), Vec::new(), true);
+        assert!(contract.get_action_history(accounts(1), "124".to_string()).is_empty());
+
+        contract.get_user_trust_actions("coreto_website".to_string(), "did:near:charlie".to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"DID opted out of the source."#)]
+    fn save_action_excluded_source() {
+        let mut contract = get_contract_with_near_did_action();
+
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), vec![accounts(1)], false);
+
+        testing_env!(get_context(accounts(1)).build());
+        save_mocked_action(&mut contract, "did:near:charlie", "reaction", "126");
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Not the DID controller."#)]
+    fn set_consent_not_controller() {
+        let mut contract = get_contract_with_near_did_action();
+
+        testing_env!(get_context(accounts(3)).build());
+        set_mocked_consent(&mut contract, "did:near:charlie".to_string(), Vec::new(), true);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Duplicate action."#)]
+    fn save_action_duplicate() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        save_mocked_action(&mut contract, "did:mock:accounts(2)", "reaction", "124");
+        save_mocked_action(&mut contract, "DID:mock:accounts(2)", "reaction", "124");
+    }
+
+    #[test]
+    fn save_action_event() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        save_mocked_action(&mut contract, "DID:mock:accounts(2)", "reaction", "124");
+
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"action_saved","data":{"source":"bob","account_did":"did:mock:accounts(2)","action_type":"reaction","identifier":"124","trust":10.0,"performance":5.0}}"#
+        );
+    }
+
+    #[test]
+    fn save_actions_batch_event() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.save_actions_batch(get_mocked_batch(), None);
+
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"batch_saved","data":{"source":"bob","batch_id":0,"actions_count":1,"dids_count":1}}"#
+        );
+    }
+
+    #[test]
+    fn save_actions_batch_event_long_dids() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        let batch: Vec<CoretoTPActionRequestData> = (0..70)
+            .map(|index| get_mocked_batch_item(&format!("did:mock:{}{}", "x".repeat(220), index), 10.0, "1"))
+            .collect();
+        testing_env!(get_context(accounts(1)).prepaid_gas(Gas(200_000_000_000_000)).build());
+        let result = contract.save_actions_batch(batch, Some(true));
+
+        assert_eq!(result.next_index, None);
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"batch_saved","data":{"source":"bob","batch_id":0,"actions_count":70,"dids_count":70}}"#
+        );
+    }
+
+    #[test]
+    fn configuration_events() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = CoretoTPLedger::default();
+
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        contract.grant_role(accounts(2), CoretoTPRole::Moderator);
+        contract.pause(vec![CoretoTPPauseCategory::Disputes]);
+        contract.remove_source(accounts(1), None);
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"source_added","data":{"source":"bob","source_label":"coreto_website"}}"#,
+                r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"role_granted","data":{"account_id":"charlie","role":"Moderator"}}"#,
+                r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"paused","data":{"categories":["Disputes"]}}"#,
+                r#"EVENT_JSON:{"standard":"coreto_tp","version":"1.0.0","event":"source_removed","data":{"source":"bob","cleanup":"Keep"}}"#,
+            ]
+        );
+    }
+
+    fn get_mocked_batch_item(account_did: &str, trust: f32, identifier: &str) -> CoretoTPActionRequestData {
+        CoretoTPActionRequestData {
+            action_type: "reaction".to_string(),
+            performance: 10.0,
+            trust,
+            action_date: "1640995200".to_string(),
+            account_did: account_did.to_string(),
+            identifier: identifier.to_string(),
+        }
+    }
+
+    #[test]
+    fn save_actions_batch_partial() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        let result = contract.save_actions_batch(
+            vec![
+                get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+                get_mocked_batch_item("accounts(3)", 10.0, "125"),
+                get_mocked_batch_item("did:mock:accounts(3)", 1000.0, "126"),
+                get_mocked_batch_item("did:mock:accounts(2)", 10.0, "123"),
+                get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+            ],
+            Some(true),
+        );
+
+        assert_eq!(result.batch_id, 0);
+        assert_eq!(
+            result.results,
+            vec![
+                CoretoTPItemResult::accepted(),
+                CoretoTPItemResult::rejected(CoretoTPErrorCode::InvalidDid),
+                CoretoTPItemResult::rejected(CoretoTPErrorCode::TrustOutOfBounds),
+                CoretoTPItemResult::duplicate(),
+                CoretoTPItemResult::duplicate(),
+            ]
+        );
+        assert_eq!(
+            contract.get_user_actions("coreto_website".to_string(), "did:mock:accounts(3)".to_string(), None).len(),
+            1
+        );
+
+        let result = contract.save_actions_batch(vec![get_mocked_batch_item("did:mock:accounts(3)", 10.0, "125")], None);
+        assert_eq!(result.batch_id, 1);
+        assert_eq!(result.results, vec![CoretoTPItemResult::accepted()]);
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Trust out of bounds."#)]
+    fn save_actions_batch_not_partial() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.save_actions_batch(
+            vec![
+                get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+                get_mocked_batch_item("did:mock:accounts(3)", 1000.0, "125"),
+            ],
+            Some(false),
+        );
+    }
+
+    #[test]
+    fn get_batch() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        let batch = vec![
+            get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+            get_mocked_batch_item("did:mock:accounts(3)", 1000.0, "125"),
+            get_mocked_batch_item("did:mock:accounts(4)", 10.0, "126"),
+        ];
+        let content_hash = env::sha256_array(&batch.try_to_vec().unwrap());
+
+        let batch_id = contract.save_actions_batch(batch, Some(true)).batch_id;
+
+        let batch = contract.get_batch(batch_id).unwrap();
+        assert_eq!(batch.source, accounts(1));
+        assert_eq!(batch.items_count, 3);
+        assert_eq!(batch.actions_count, 2);
+        assert_eq!(batch.content_hash, content_hash.into());
+        assert!(contract.get_batch(batch_id + 1).is_none());
+
+        let actions = contract.get_batch_actions(batch_id, None, None);
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[1].identifier, "126");
+        assert_eq!(actions[1].batch_id, Some(batch_id));
+        assert_eq!(contract.get_batch_actions(batch_id, Some(1), Some(1)).len(), 1);
+
+        let actions = contract.get_user_actions("coreto_website".to_string(), "did:mock:accounts(2)".to_string(), None);
+        assert_eq!(actions[0].batch_id, None);
+    }
+
+    #[test]
+    fn retract_batch() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        let batch_id = contract.save_actions_batch(
+            vec![
+                get_mocked_batch_item("did:mock:accounts(2)", 10.0, "124"),
+                get_mocked_batch_item("did:mock:accounts(2)", 20.0, "125"),
+            ],
+            None,
+        ).batch_id;
+
+        contract.retract_batch(batch_id, "Sync bug".to_string());
+
+        let actions = contract.get_batch_actions(batch_id, None, None);
+        assert!(actions.iter().all(|action| action.retraction.is_some()));
+        assert_eq!(
+            contract.get_user_trust("coreto_website".to_string(), "did:mock:accounts(2)".to_string()),
+            CoretoTPScore::Value(10.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Batch not found."#)]
+    fn retract_batch_other_source() {
+        let mut contract = get_contract_with_two_sources();
+        testing_env!(get_context(accounts(1)).build());
+        let batch_id = contract.save_actions_batch(get_mocked_batch(), None).batch_id;
+
+        testing_env!(get_context(accounts(2)).build());
+        contract.retract_batch(batch_id, "Sync bug".to_string());
+    }
+
+    #[test]
+    fn save_actions_batch_out_of_gas() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        let batch = vec![
+            get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+            get_mocked_batch_item("did:mock:accounts(3)", 10.0, "125"),
+        ];
+
+        testing_env!(get_context(accounts(1)).prepaid_gas(INGESTION_GAS_RESERVE).build());
+        let result = contract.save_actions_batch(batch, None);
+
+        assert!(result.results.is_empty());
+        assert_eq!(result.next_index, Some(0));
+        let receipt = contract.get_batch(result.batch_id).unwrap();
+        assert_eq!(receipt.items_count, 0);
+        assert_eq!(receipt.actions_count, 0);
+
+        testing_env!(get_context(accounts(1)).build());
+        let result = contract.save_actions_batch(get_mocked_batch(), None);
+        assert_eq!(result.results.len(), 1);
+        assert_eq!(result.next_index, None);
+    }
+
+    #[test]
+    fn save_actions_batch_resume() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        let get_batch = |from_index: usize| -> Vec<CoretoTPActionRequestData> {
+            (from_index..150)
+                .map(|index| get_mocked_batch_item(&format!("did:mock:{}", index), 10.0, &index.to_string()))
+                .collect()
+        };
+        let batch = get_batch(0);
+
+        testing_env!(get_context(accounts(1)).prepaid_gas(Gas(100_000_000_000_000)).build());
+        let first = contract.save_actions_batch(get_batch(0), None);
+
+        let next_index = first.next_index.unwrap() as usize;
+        assert!(next_index > 0 && next_index < batch.len());
+        assert_eq!(first.results.len(), next_index);
+        let receipt = contract.get_batch(first.batch_id).unwrap();
+        assert_eq!(receipt.items_count, next_index as u64);
+        assert_eq!(receipt.actions_count, next_index as u64);
+        assert_eq!(receipt.content_hash, env::sha256_array(&batch[..next_index].try_to_vec().unwrap()).into());
+
+        testing_env!(get_context(accounts(1)).prepaid_gas(Gas(200_000_000_000_000)).build());
+        let second = contract.save_actions_batch(get_batch(next_index), None);
+
+        assert_eq!(second.next_index, None);
+        assert_eq!(second.results.len(), batch.len() - next_index);
+        assert!(second.results.iter().all(|result| result.status == CoretoTPItemStatus::Accepted));
+        assert_eq!(contract.get_batch(second.batch_id).unwrap().items_count, (batch.len() - next_index) as u64);
+    }
+
+    #[test]
+    fn save_actions_batch_borsh() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        let result = contract.save_actions_batch_borsh(get_mocked_batch(), Some(true));
+
+        assert_eq!(result.results, vec![CoretoTPItemResult::accepted()]);
+        assert_eq!(
+            contract.get_user_actions("coreto_website".to_string(), "did:mock:accounts(3)".to_string(), None).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn validate_actions_batch() {
+        let contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(5)).build());
+        let results = contract.validate_actions_batch(
+            accounts(1),
+            vec![
+                get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+                get_mocked_batch_item("did:mock:accounts(3)", 10.0, "124"),
+                get_mocked_batch_item("did:mock:accounts(2)", 10.0, "123"),
+                get_mocked_batch_item("did:mock:accounts(3)", -1000.0, "125"),
+                CoretoTPActionRequestData {
+                    action_type: "article".to_string(),
+                    ..get_mocked_batch_item("did:mock:accounts(3)", 10.0, "126")
+                },
+            ],
+        );
+
+        assert_eq!(
+            results,
+            vec![
+                CoretoTPItemResult::accepted(),
+                CoretoTPItemResult::duplicate(),
+                CoretoTPItemResult::duplicate(),
+                CoretoTPItemResult::rejected(CoretoTPErrorCode::TrustOutOfBounds),
+                CoretoTPItemResult::rejected(CoretoTPErrorCode::ActionTypeNotRegistered),
+            ]
+        );
+        assert!(!contract.tp_infos.contains_key(&"did:mock:accounts(3)".to_string()));
+        assert!(contract.get_batch(0).is_none());
+    }
+
+    #[test]
+    fn validate_actions_batch_paused() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(0)).build());
+        contract.pause(vec![CoretoTPPauseCategory::Ingestion]);
+
+        assert_eq!(
+            contract.validate_actions_batch(accounts(1), get_mocked_batch()),
+            vec![CoretoTPItemResult::accepted()]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"Invalid signer wallet."#)]
+    fn validate_actions_batch_not_source() {
+        let contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        contract.validate_actions_batch(accounts(5), get_mocked_batch());
+    }
+
+    #[test]
+    fn storage_deposit() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+
+        testing_env!(get_context(accounts(1)).attached_deposit(STORAGE_DEPOSIT).build());
+        let balance = contract.storage_deposit(None, None);
+
+        assert_eq!(balance, StorageBalance { total: STORAGE_DEPOSIT.into(), available: STORAGE_DEPOSIT.into() });
+        assert_eq!(contract.storage_balance_of(accounts(1)), Some(balance));
+        assert_eq!(contract.storage_balance_of(accounts(2)), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Source not found.")]
+    fn storage_deposit_not_source() {
+        testing_env!(get_context(accounts(2)).attached_deposit(STORAGE_DEPOSIT).build());
+        let mut contract = CoretoTPLedger::default();
+
+        contract.storage_deposit(None, None);
+    }
+
+    #[test]
+    fn save_action_charges_storage() {
+        let contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        let storage_account = contract.storage_accounts.get(&accounts(1)).unwrap();
+        assert!(storage_account.used_bytes > 0);
+        assert_eq!(
+            contract.storage_balance_of(accounts(1)).unwrap().available.0,
+            STORAGE_DEPOSIT - Balance::from(storage_account.used_bytes) * env::storage_byte_cost()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient storage balance.")]
+    fn save_action_insufficient_storage() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+
+        testing_env!(get_context(accounts(1)).build());
+        contract.save_action(
+            "did:mock:accounts(2)".to_string(),
+            10.0,
+            10.0,
+            "reaction".to_string(),
+            "1640995200".to_string(),
+            "123".to_string(),
+        );
+    }
+
+    #[test]
+    fn storage_withdraw() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+        let available = contract.storage_balance_of(accounts(1)).unwrap().available;
+
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        let balance = contract.storage_withdraw(None);
+
+        assert_eq!(balance.available.0, 0);
+        assert_eq!(balance.total.0, STORAGE_DEPOSIT - available.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount exceeds the available storage balance.")]
+    fn storage_withdraw_too_much() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.storage_withdraw(Some(STORAGE_DEPOSIT.into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage in use.")]
+    fn storage_unregister_in_use() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.storage_unregister(None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient storage balance.")]
+    fn register_action_type_insufficient_storage() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+
+        testing_env!(get_context(accounts(1)).build());
+        register_mocked_action_type(&mut contract, accounts(1), "reaction");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid action type name.")]
+    fn register_action_type_name_too_long() {
+        testing_env!(get_context(accounts(0)).build());
+        let mut contract = CoretoTPLedger::default();
+        contract.add_source(accounts(1), "coreto_website".to_string());
+        deposit_storage(&mut contract, accounts(1));
+
+        register_mocked_action_type(&mut contract, accounts(1), &"x".repeat(65));
+    }
+
+    #[test]
+    fn validate_actions_batch_insufficient_storage() {
+        let mut contract = get_contact_with_mocked_source_and_action(
+            "coreto_website".to_string(),
+            "reaction".to_string(),
+        );
+
+        testing_env!(get_context(accounts(1)).attached_deposit(1).build());
+        contract.storage_withdraw(None);
+
+        assert_eq!(
+            contract.validate_actions_batch(accounts(1), get_mocked_batch()),
+            vec![CoretoTPItemResult::rejected(CoretoTPErrorCode::InsufficientStorage)]
+        );
     }
 }
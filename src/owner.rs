@@ -0,0 +1,62 @@
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::*;
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// With a `did_salt`, DIDs are stored under their salted hash instead
+    /// of in clear.
+    #[init]
+    pub fn new(owner_id: AccountId, did_salt: Option<Base64VecU8>) -> Self {
+        require!(!env::state_exists(), "Already initialized.");
+        require!(
+            did_salt.as_ref().is_none_or(|did_salt| !did_salt.0.is_empty()),
+            "Invalid DID salt."
+        );
+
+        Self {
+            owner_id,
+            did_salt: did_salt.map(|did_salt| did_salt.0),
+            ..Self::default()
+        }
+    }
+
+    /// First step of an ownership transfer. Proposing again replaces the
+    /// pending owner.
+    pub fn propose_owner(&mut self, new_owner_id: AccountId) {
+        self.assert_owner();
+
+        CoretoTPEvent::OwnerProposed { owner_id: &new_owner_id }.emit();
+        self.pending_owner_id = Some(new_owner_id);
+    }
+
+    /// Second step of an ownership transfer, called by the proposed owner.
+    pub fn accept_ownership(&mut self) {
+        require!(
+            self.pending_owner_id == Some(env::predecessor_account_id()),
+            "Not the pending owner."
+        );
+
+        self.owner_id = self.pending_owner_id.take().unwrap();
+
+        CoretoTPEvent::OwnershipTransferred { owner_id: &self.owner_id }.emit();
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+}
+
+impl CoretoTPLedger {
+    pub(crate) fn assert_owner(&self) {
+        require!(
+            env::predecessor_account_id() == self.owner_id,
+            "Owner only."
+        );
+    }
+}
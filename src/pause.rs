@@ -0,0 +1,68 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{near_bindgen, require};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Groups of methods that can be paused. Views are never paused, nor are
+/// redactions and the storage methods, so erasure requests and withdrawals
+/// can still be served.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPPauseCategory {
+    /// Every category below.
+    All,
+    /// `save_action`, `save_actions_batch`, `save_actions_batch_borsh` and
+    /// `save_signed_actions_batch`.
+    Ingestion,
+    /// `add_source`, `remove_source`, `continue_source_cleanup`, the
+    /// submitter and action type methods, `set_source_public_key` and
+    /// `set_signer_submission`.
+    SourceManagement,
+    /// `retract_action`, `amend_action` and `retract_batch`.
+    Corrections,
+    /// Opening, freezing and resolving disputes.
+    Disputes,
+    /// `claim_did`, `release_did`, `merge_did`, `unmerge_did` and
+    /// `set_consent`.
+    DidRegistry,
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    pub fn pause(&mut self, categories: Vec<CoretoTPPauseCategory>) {
+        self.assert_owner();
+
+        for category in categories.iter() {
+            if !self.paused.contains(category) {
+                self.paused.push(*category);
+            }
+        }
+
+        CoretoTPEvent::Paused { categories: &categories }.emit();
+    }
+
+    pub fn unpause(&mut self, categories: Vec<CoretoTPPauseCategory>) {
+        self.assert_owner();
+
+        self.paused.retain(|category| !categories.contains(category));
+
+        CoretoTPEvent::Unpaused { categories: &categories }.emit();
+    }
+
+    pub fn get_paused(&self) -> Vec<CoretoTPPauseCategory> {
+        self.paused.clone()
+    }
+
+    pub fn is_paused(&self, category: CoretoTPPauseCategory) -> bool {
+        self.paused.contains(&CoretoTPPauseCategory::All) || self.paused.contains(&category)
+    }
+}
+
+impl CoretoTPLedger {
+    pub(crate) fn assert_not_paused(&self, category: CoretoTPPauseCategory) {
+        require!(
+            !self.is_paused(category),
+            format!("{:?} is paused.", category)
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::Base58CryptoHash;
+use near_sdk::{env, near_bindgen, require, AccountId};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Maximum number of redactions returned by one paginated view call.
+const MAX_REDACTIONS_PAGE: u64 = 100;
+
+/// What is kept of a DID's actions once it is redacted.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPRedactionMode {
+    /// Per-source totals of the actions, without dates or identifiers.
+    Summary,
+    /// Only the number of redacted actions.
+    Tombstone,
+}
+
+/// Audit record of a redaction. The DID itself is not kept, only the
+/// sha256 hash of its key.
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+pub struct CoretoTPRedaction {
+    pub(crate) id: u64,
+    pub(crate) did_hash: Base58CryptoHash,
+    pub(crate) mode: CoretoTPRedactionMode,
+    pub(crate) redacted_by: AccountId,
+    pub(crate) requested_date: String,
+    /// Set once every action of the DID is redacted.
+    pub(crate) completed_date: Option<String>,
+    pub(crate) actions_count: u64,
+    /// Totals per source, kept in `Summary` mode.
+    pub(crate) summaries: Vec<CoretoTPActionsSummary>,
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Erases the DID's actions, archives, indexes, consent history,
+    /// registry entry and merges, and closes its disputes, keeping only what
+    /// `mode` allows in the audit log. DIDs of methods no longer allowed can
+    /// still be redacted. Runs in gas-bounded chunks; returns the redaction
+    /// id.
+    pub fn redact_did(&mut self, account_did: String, mode: Option<CoretoTPRedactionMode>) -> u64 {
+        self.assert_role(CoretoTPRole::Moderator);
+        let account_did = self.redaction_key(&account_did);
+        require!(self.has_did_state(&account_did), "AccountDID not found.");
+        require!(!self.redaction_jobs.contains_key(&account_did), "Redaction already in progress.");
+
+        let id = self.redactions.len();
+        self.redactions.push(&CoretoTPRedaction {
+            id,
+            did_hash: env::sha256_array(account_did.as_bytes()).into(),
+            mode: mode.unwrap_or(CoretoTPRedactionMode::Summary),
+            redacted_by: env::predecessor_account_id(),
+            requested_date: env::block_timestamp().to_string(),
+            completed_date: None,
+            actions_count: 0,
+            summaries: Vec::new(),
+        });
+        self.redaction_jobs.insert(&account_did, &id);
+
+        CoretoTPEvent::RedactionRequested { redaction_id: id }.emit();
+
+        self.process_redaction(&account_did);
+        id
+    }
+
+    /// Continues a redaction that ran out of gas. Returns the number of
+    /// actions and consents left.
+    pub fn continue_redaction(&mut self, account_did: String) -> u64 {
+        self.assert_role(CoretoTPRole::Moderator);
+        let account_did = self.redaction_key(&account_did);
+        require!(self.redaction_jobs.contains_key(&account_did), "No redaction in progress.");
+
+        self.process_redaction(&account_did)
+    }
+
+    pub fn get_redaction(&self, redaction_id: u64) -> Option<CoretoTPRedaction> {
+        self.redactions.get(redaction_id)
+    }
+
+    pub fn get_redactions(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<CoretoTPRedaction> {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(MAX_REDACTIONS_PAGE).min(MAX_REDACTIONS_PAGE);
+
+        (from_index..self.redactions.len().min(from_index.saturating_add(limit)))
+            .filter_map(|redaction_id| self.redactions.get(redaction_id))
+            .collect()
+    }
+}
+
+impl CoretoTPLedger {
+    /// The key the DID is stored under, without checking its method against
+    /// the allowed ones.
+    fn redaction_key(&self, account_did: &str) -> String {
+        let account_did = require_ok(dids::normalize_did(account_did).ok_or(CoretoTPErrorCode::InvalidDid));
+        self.hash_did(&account_did)
+    }
+
+    /// Whether the ledger holds anything about the DID.
+    fn has_did_state(&self, account_did: &String) -> bool {
+        self.tp_infos.contains_key(account_did)
+            || self.consents.contains_key(account_did)
+            || self.did_controllers.contains_key(account_did)
+            || self.did_disputes.contains_key(account_did)
+            || self.did_aliases.contains_key(account_did)
+            || self.canonical_dids.contains_key(account_did)
+    }
+
+    /// Removes the DID's actions from the end of its history, then its
+    /// consents, until none are left or the gas reserve is reached, then
+    /// drops the DID's remaining state. The storage of each action is
+    /// credited to its source. Returns the number of actions and consents
+    /// left.
+    fn process_redaction(&mut self, account_did: &String) -> u64 {
+        let redaction_id = self.redaction_jobs.get(account_did).unwrap();
+        let mut redaction = self.redactions.get(redaction_id).unwrap();
+
+        if let Some(mut tp_info) = self.tp_infos.get(account_did) {
+            while env::used_gas() + CLEANUP_GAS_RESERVE < env::prepaid_gas() {
+                let initial_storage_usage = env::storage_usage();
+                let action = match tp_info.actions.pop() {
+                    Some(action) => action,
+                    None => break,
+                };
+
+                self.unindex_action(account_did, &action);
+                self.remove_source_did(&action.source, account_did);
+                if let Some(dispute_id) = action.dispute_id {
+                    self.void_dispute(dispute_id);
+                }
+                self.credit_storage(&action.source, initial_storage_usage);
+                redaction.actions_count += 1;
+                if redaction.mode == CoretoTPRedactionMode::Summary && action.retraction.is_none() {
+                    Self::add_to_summaries(&mut redaction.summaries, &action);
+                }
+            }
+
+            if !tp_info.actions.is_empty() {
+                self.tp_infos.insert(account_did, &tp_info);
+                self.redactions.replace(redaction_id, &redaction);
+                let consents = self.consents.get(account_did).map_or(0, |consents| consents.len());
+                return tp_info.actions.len() + consents;
+            }
+
+            if redaction.mode == CoretoTPRedactionMode::Summary {
+                redaction.summaries.append(&mut tp_info.archives);
+            }
+            self.tp_infos.remove(account_did);
+        }
+
+        if let Some(mut consents) = self.consents.get(account_did) {
+            while env::used_gas() + CLEANUP_GAS_RESERVE < env::prepaid_gas() && consents.pop().is_some() {}
+
+            if !consents.is_empty() {
+                self.consents.insert(account_did, &consents);
+                self.redactions.replace(redaction_id, &redaction);
+                return consents.len();
+            }
+            self.consents.remove(account_did);
+        }
+        self.forget_did(account_did);
+
+        redaction.completed_date = Some(env::block_timestamp().to_string());
+        self.redactions.replace(redaction_id, &redaction);
+        self.redaction_jobs.remove(account_did);
+
+        CoretoTPEvent::RedactionCompleted { redaction_id, actions_count: redaction.actions_count }.emit();
+        0
+    }
+
+    fn unindex_action(&mut self, account_did: &String, action: &CoretoTPAction) {
+        if let Some(batch_id) = action.batch_id {
+            if let Some(mut action_refs) = self.batch_actions.get(&batch_id) {
+                action_refs.retain(|(_, action_ref)| action_ref.account_did != *account_did);
+                self.batch_actions.insert(&batch_id, &action_refs);
+            }
+        }
+
+        let key = (action.source.clone(), action.identifier.clone());
+        let mut refs = match self.action_index.get(&key) {
+            Some(refs) => refs,
+            None => return,
+        };
+
+        refs.retain(|action_ref| action_ref.account_did != *account_did);
+        if refs.is_empty() {
+            self.action_index.remove(&key);
+        } else {
+            self.action_index.insert(&key, &refs);
+        }
+    }
+
+    fn add_to_summaries(summaries: &mut Vec<CoretoTPActionsSummary>, action: &CoretoTPAction) {
+        match summaries.iter_mut().find(|summary| summary.source == action.source) {
+            Some(summary) => {
+                summary.actions_count += 1;
+                summary.trust += action.trust;
+                summary.performance += action.performance;
+            }
+            None => summaries.push(CoretoTPActionsSummary {
+                source_label: action.source_label.clone(),
+                source: action.source.clone(),
+                actions_count: 1,
+                trust: action.trust,
+                performance: action.performance,
+                archive_date: env::block_timestamp().to_string(),
+            }),
+        }
+    }
+
+    /// Removes the DID from the source's DIDs, unless a cleanup of the
+    /// source is walking them; the cleanup skips DIDs without a history.
+    fn remove_source_did(&mut self, source: &AccountId, account_did: &String) {
+        if self.source_cleanups.contains_key(source) {
+            return;
+        }
+
+        if let Some(mut source_dids) = self.source_dids.get(source) {
+            if source_dids.remove(account_did) {
+                self.source_dids.insert(source, &source_dids);
+            }
+        }
+    }
+
+    /// Voids the DID's pending disputes and strips them of the DID, their
+    /// opener, reason and evidence, and drops its registry entry and merges.
+    fn forget_did(&mut self, account_did: &String) {
+        for dispute_id in self.did_disputes.remove(account_did).unwrap_or_default() {
+            self.void_dispute(dispute_id);
+
+            let mut dispute = self.disputes.get(dispute_id).unwrap();
+            dispute.account_did = String::new();
+            dispute.opened_by = None;
+            dispute.reason = String::new();
+            dispute.evidence_hash = None;
+            self.disputes.replace(dispute_id, &dispute);
+        }
+
+        self.unbind_did(account_did);
+        self.unlink_did(account_did);
+    }
+}
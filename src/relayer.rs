@@ -0,0 +1,100 @@
+use ed25519_dalek::Verifier;
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::{env, near_bindgen, require, AccountId, CurveType, PublicKey};
+
+use crate::*;
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Registers the ed25519 key the calling source signs relayed batches
+    /// with. `None` disables relayed submission.
+    pub fn set_source_public_key(&mut self, public_key: Option<PublicKey>) {
+        self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
+        let source_id = env::predecessor_account_id();
+        require!(
+            self.sources.contains_key(&source_id),
+            "Source not found."
+        );
+        require!(
+            public_key.as_ref().is_none_or(|key| key.curve_type() == CurveType::ED25519),
+            "Only ed25519 keys are supported."
+        );
+        let initial_storage_usage = env::storage_usage();
+
+        let mut source = self.sources.get(&source_id).unwrap();
+        source.public_key = public_key;
+        self.sources.insert(&source_id, &source);
+        self.charge_storage(&source_id, initial_storage_usage);
+
+        CoretoTPEvent::SourcePublicKeyUpdated {
+            source: &source_id,
+            public_key: source.public_key.as_ref(),
+        }
+        .emit();
+    }
+
+    /// Saves a batch signed by the source and submitted by any account.
+    ///
+    /// The signature covers the sha256 hash of the Borsh-serialized
+    /// `(ledger_account_id, source, nonce, batch)` tuple. `nonce` must be
+    /// greater than the source's last relayed nonce. The nonce is used up
+    /// even if the batch stops at `next_index`; the remaining items have to
+    /// be signed again with a new nonce.
+    pub fn save_signed_actions_batch(
+        &mut self,
+        source: AccountId,
+        batch: Vec<CoretoTPActionRequestData>,
+        nonce: u64,
+        signature: Base64VecU8,
+    ) -> CoretoTPBatchResult {
+        self.assert_not_paused(CoretoTPPauseCategory::Ingestion);
+        require!(
+            self.sources.contains_key(&source),
+            "Source not found."
+        );
+
+        let mut source_data = self.sources.get(&source).unwrap();
+        require!(source_data.public_key.is_some(), "Source has no public key.");
+        require!(nonce > source_data.nonce, "Invalid nonce.");
+
+        let message = signed_batch_hash(&env::current_account_id(), &source, nonce, &batch);
+        require!(
+            verify_ed25519(source_data.public_key.as_ref().unwrap(), &message, &signature.0),
+            "Invalid signature."
+        );
+
+        source_data.nonce = nonce;
+        self.sources.insert(&source, &source_data);
+
+        self.save_batch(&source, &source_data, &batch, false)
+    }
+}
+
+pub(crate) fn signed_batch_hash(
+    ledger: &AccountId,
+    source: &AccountId,
+    nonce: u64,
+    batch: &[CoretoTPActionRequestData],
+) -> Vec<u8> {
+    let payload = (ledger, source, nonce, batch).try_to_vec().unwrap();
+    env::sha256(&payload)
+}
+
+/// Checks an ed25519 `signature` of `message` against a NEAR public key.
+pub(crate) fn verify_ed25519(public_key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    if public_key.curve_type() != CurveType::ED25519 {
+        return false;
+    }
+
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..]) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature = match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+
+    public_key.verify(message, &signature).is_ok()
+}
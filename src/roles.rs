@@ -0,0 +1,80 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, require, AccountId};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Permissions that can be delegated by the owner. The owner implicitly
+/// holds every role.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CoretoTPRole {
+    Owner,
+    SourceManager,
+    WeightManager,
+    Moderator,
+    Arbiter,
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    pub fn grant_role(&mut self, account_id: AccountId, role: CoretoTPRole) {
+        self.assert_owner();
+        require!(
+            role != CoretoTPRole::Owner,
+            "Owner role is transferred with propose_owner."
+        );
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        require!(!roles.contains(&role), "Role already granted.");
+
+        roles.push(role);
+        self.roles.insert(&account_id, &roles);
+
+        CoretoTPEvent::RoleGranted { account_id: &account_id, role }.emit();
+    }
+
+    pub fn revoke_role(&mut self, account_id: AccountId, role: CoretoTPRole) {
+        self.assert_owner();
+
+        let mut roles = self.roles.get(&account_id).unwrap_or_default();
+        require!(roles.contains(&role), "Role not granted.");
+
+        roles.retain(|granted| *granted != role);
+        if roles.is_empty() {
+            self.roles.remove(&account_id);
+        } else {
+            self.roles.insert(&account_id, &roles);
+        }
+
+        CoretoTPEvent::RoleRevoked { account_id: &account_id, role }.emit();
+    }
+
+    pub fn has_role(&self, account_id: AccountId, role: CoretoTPRole) -> bool {
+        if account_id == self.owner_id {
+            return true;
+        }
+
+        self.roles
+            .get(&account_id)
+            .is_some_and(|roles| roles.contains(&role))
+    }
+}
+
+impl CoretoTPLedger {
+    pub(crate) fn assert_role(&self, role: CoretoTPRole) {
+        require!(
+            self.has_role(env::predecessor_account_id(), role),
+            format!("Missing {:?} role.", role)
+        );
+    }
+
+    pub(crate) fn assert_any_role(&self, roles: &[CoretoTPRole]) {
+        require!(
+            roles.iter().any(|role| self.has_role(env::predecessor_account_id(), *role)),
+            format!(
+                "Missing {} role.",
+                roles.iter().map(|role| format!("{:?}", role)).collect::<Vec<_>>().join(" or ")
+            )
+        );
+    }
+}
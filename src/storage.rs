@@ -0,0 +1,204 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U128;
+use near_sdk::{assert_one_yocto, env, near_bindgen, require, AccountId, Balance, Promise, StorageUsage};
+use serde::Serialize;
+
+use crate::*;
+
+/// Estimated bytes a batch receipt uses, besides its items.
+pub(crate) const BATCH_STORAGE_ESTIMATE: StorageUsage = 500;
+/// Estimated bytes a batch item uses besides its strings: the action, its
+/// index entries and the storage records that hold them.
+const ITEM_STORAGE_ESTIMATE: StorageUsage = 500;
+
+/// NEP-145 storage balance of a source.
+#[derive(Serialize, PartialEq, Debug)]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+#[derive(Serialize)]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+/// Deposit of a source and the storage its writes use.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CoretoTPStorageAccount {
+    pub(crate) total: Balance,
+    pub(crate) used_bytes: StorageUsage,
+}
+
+impl CoretoTPStorageAccount {
+    fn available(&self) -> Balance {
+        self.total.saturating_sub(Balance::from(self.used_bytes) * env::storage_byte_cost())
+    }
+
+    fn balance(&self) -> StorageBalance {
+        StorageBalance {
+            total: self.total.into(),
+            available: self.available().into(),
+        }
+    }
+}
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Adds the attached deposit to the storage balance of a source, the
+    /// caller by default. Registration is free, so with `registration_only`
+    /// the whole deposit is refunded.
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        require!(self.sources.contains_key(&account_id), "Source not found.");
+
+        let mut deposit = env::attached_deposit();
+        if registration_only.unwrap_or(false) && deposit > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(deposit);
+            deposit = 0;
+        }
+
+        let mut storage_account = self.storage_account(&account_id);
+        storage_account.total += deposit;
+        self.storage_accounts.insert(&account_id, &storage_account);
+
+        storage_account.balance()
+    }
+
+    /// Withdraws `amount`, or the whole available balance, to the calling
+    /// source. Requires exactly 1 yoctoNEAR attached.
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let storage_account = self.storage_accounts.get(&account_id);
+        require!(storage_account.is_some(), "Storage account not registered.");
+
+        let mut storage_account = storage_account.unwrap();
+        let amount = amount.map_or_else(|| storage_account.available(), |amount| amount.0);
+        require!(amount <= storage_account.available(), "Amount exceeds the available storage balance.");
+
+        storage_account.total -= amount;
+        self.storage_accounts.insert(&account_id, &storage_account);
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+
+        storage_account.balance()
+    }
+
+    /// Refunds the caller's balance and removes its storage account. Fails
+    /// while its writes still use storage, which cannot be forced.
+    #[payable]
+    pub fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+        assert_one_yocto();
+        require!(!force.unwrap_or(false), "Forced unregistration is not supported.");
+
+        let account_id = env::predecessor_account_id();
+        let storage_account = match self.storage_accounts.get(&account_id) {
+            Some(storage_account) => storage_account,
+            None => return false,
+        };
+        require!(storage_account.used_bytes == 0, "Storage in use.");
+
+        self.storage_accounts.remove(&account_id);
+        if storage_account.total > 0 {
+            Promise::new(account_id).transfer(storage_account.total);
+        }
+
+        true
+    }
+
+    pub fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_accounts
+            .get(&account_id)
+            .map(|storage_account| storage_account.balance())
+    }
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: 0.into(),
+            max: None,
+        }
+    }
+}
+
+impl CoretoTPLedger {
+    fn storage_account(&self, account_id: &AccountId) -> CoretoTPStorageAccount {
+        self.storage_accounts
+            .get(account_id)
+            .unwrap_or(CoretoTPStorageAccount { total: 0, used_bytes: 0 })
+    }
+
+    pub(crate) fn storage_available(&self, source: &AccountId) -> Balance {
+        self.storage_account(source).available()
+    }
+
+    /// Estimated bytes the item uses once written for the DID key. The DID
+    /// and identifier are stored several times, in the action's indexes.
+    pub(crate) fn estimate_item_storage(account_did: &str, data: &CoretoTPActionRequestData) -> StorageUsage {
+        let bytes = 5 * account_did.len()
+            + 3 * data.identifier.len()
+            + 2 * data.action_type.len()
+            + data.action_date.len();
+
+        ITEM_STORAGE_ESTIMATE + bytes as StorageUsage
+    }
+
+    /// Requires the attached deposit to cover the storage used since
+    /// `initial_storage_usage` and refunds the rest to the caller.
+    pub(crate) fn charge_attached_deposit(&self, initial_storage_usage: StorageUsage) {
+        let cost = Balance::from(env::storage_usage().saturating_sub(initial_storage_usage)) * env::storage_byte_cost();
+        require!(env::attached_deposit() >= cost, "Insufficient deposit for storage.");
+
+        let refund = env::attached_deposit() - cost;
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    /// Refunds the account for the storage freed since
+    /// `initial_storage_usage`.
+    pub(crate) fn refund_freed_storage(&self, account_id: &AccountId, initial_storage_usage: StorageUsage) {
+        let refund = Balance::from(initial_storage_usage.saturating_sub(env::storage_usage())) * env::storage_byte_cost();
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+    }
+
+    /// Credits the source for the storage freed since `initial_storage_usage`
+    /// by removing its writes.
+    pub(crate) fn credit_storage(&mut self, source: &AccountId, initial_storage_usage: StorageUsage) {
+        let freed_bytes = initial_storage_usage.saturating_sub(env::storage_usage());
+        if freed_bytes == 0 {
+            return;
+        }
+
+        if let Some(mut storage_account) = self.storage_accounts.get(source) {
+            storage_account.used_bytes = storage_account.used_bytes.saturating_sub(freed_bytes);
+            self.storage_accounts.insert(source, &storage_account);
+        }
+    }
+
+    /// Charges the source for the storage used since `initial_storage_usage`,
+    /// or credits it for the storage freed. Fails if the source's deposit
+    /// does not cover its storage.
+    pub(crate) fn charge_storage(&mut self, source: &AccountId, initial_storage_usage: StorageUsage) {
+        let mut storage_account = self.storage_account(source);
+        let storage_usage = env::storage_usage();
+
+        storage_account.used_bytes = if storage_usage >= initial_storage_usage {
+            storage_account.used_bytes + (storage_usage - initial_storage_usage)
+        } else {
+            storage_account.used_bytes.saturating_sub(initial_storage_usage - storage_usage)
+        };
+        require!(
+            Balance::from(storage_account.used_bytes) * env::storage_byte_cost() <= storage_account.total,
+            CoretoTPErrorCode::InsufficientStorage.message()
+        );
+
+        self.storage_accounts.insert(source, &storage_account);
+    }
+}
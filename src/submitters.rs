@@ -0,0 +1,103 @@
+use near_sdk::collections::UnorderedSet;
+use near_sdk::{env, near_bindgen, require, AccountId};
+
+use crate::*;
+
+#[near_bindgen]
+impl CoretoTPLedger {
+    /// Authorises `submitter` to write on behalf of `source`. Its actions
+    /// are attributed to the source. Callable by the source or a
+    /// SourceManager; the source pays for its storage.
+    pub fn add_source_submitter(&mut self, source: AccountId, submitter: AccountId) {
+        self.assert_source_admin(&source);
+        require!(
+            !self.sources.contains_key(&submitter),
+            "Submitter is a source."
+        );
+        require!(
+            !self.submitters.contains_key(&submitter),
+            "Submitter already exists."
+        );
+        let initial_storage_usage = env::storage_usage();
+
+        let mut source_submitters = self.source_submitters
+            .get(&source)
+            .unwrap_or_else(|| UnorderedSet::new(StorageKeys::SourceSubmittersSetKey {
+                source_hash: env::sha256_array(source.as_bytes()),
+            }));
+
+        source_submitters.insert(&submitter);
+        self.source_submitters.insert(&source, &source_submitters);
+        self.submitters.insert(&submitter, &source);
+        self.charge_storage(&source, initial_storage_usage);
+
+        CoretoTPEvent::SubmitterAdded { source: &source, submitter: &submitter }.emit();
+    }
+
+    pub fn remove_source_submitter(&mut self, source: AccountId, submitter: AccountId) {
+        self.assert_source_admin(&source);
+        require!(
+            self.submitters.get(&submitter) == Some(source.clone()),
+            "Submitter not found."
+        );
+        let initial_storage_usage = env::storage_usage();
+
+        let mut source_submitters = self.source_submitters.get(&source).unwrap();
+        source_submitters.remove(&submitter);
+        if source_submitters.is_empty() {
+            self.source_submitters.remove(&source);
+        } else {
+            self.source_submitters.insert(&source, &source_submitters);
+        }
+
+        self.submitters.remove(&submitter);
+        self.charge_storage(&source, initial_storage_usage);
+
+        CoretoTPEvent::SubmitterRemoved { source: &source, submitter: &submitter }.emit();
+    }
+
+    pub fn get_source_submitters(&self, source: AccountId) -> Vec<AccountId> {
+        require!(
+            self.sources.contains_key(&source),
+            "Source not found."
+        );
+
+        self.source_submitters
+            .get(&source)
+            .map_or_else(Vec::new, |source_submitters| source_submitters.to_vec())
+    }
+}
+
+impl CoretoTPLedger {
+    /// Returns the source an account writes for: itself if it is a source,
+    /// or the source that authorised it as a submitter.
+    pub(crate) fn source_of(&self, account_id: &AccountId) -> Option<AccountId> {
+        if self.sources.contains_key(account_id) {
+            return Some(account_id.clone());
+        }
+
+        self.submitters.get(account_id)
+    }
+
+    pub(crate) fn remove_all_submitters(&mut self, source: &AccountId) {
+        if let Some(mut source_submitters) = self.source_submitters.remove(source) {
+            for submitter in source_submitters.iter() {
+                self.submitters.remove(&submitter);
+            }
+            source_submitters.clear();
+        }
+    }
+
+    pub(crate) fn assert_source_admin(&self, source: &AccountId) {
+        self.assert_not_paused(CoretoTPPauseCategory::SourceManagement);
+        require!(
+            self.sources.contains_key(source),
+            "Source not found."
+        );
+        require!(
+            env::predecessor_account_id() == *source
+                || self.has_role(env::predecessor_account_id(), CoretoTPRole::SourceManager),
+            "Missing SourceManager role."
+        );
+    }
+}